@@ -0,0 +1,201 @@
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Placement {
+    pub page: usize,
+    pub x: u32,
+    pub y: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Page {
+    pub width: u32,
+    pub height: u32,
+}
+
+#[derive(Debug)]
+pub struct PackError(String);
+
+impl fmt::Display for PackError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for PackError {}
+
+// 天際線上的一段，由 x 開始寬度為 width，高度為 y 以上的空間皆可使用
+#[derive(Debug, Clone, Copy)]
+struct Segment {
+    x: u32,
+    y: u32,
+    width: u32,
+}
+
+#[derive(Debug)]
+struct Skyline {
+    size: u32,
+    segments: Vec<Segment>,
+    // 實際使用的範圍（不含間距）
+    used_width: u32,
+    used_height: u32,
+}
+
+impl Skyline {
+    fn new(size: u32) -> Self {
+        Self {size, segments: vec![Segment {x: 0, y: 0, width: size}], used_width: 0, used_height: 0}
+    }
+
+    // 以 bottom-left 法則尋找位置：優先選擇 y 最小，其次 x 最小的位置
+    fn find(&self, width: u32, height: u32) -> Option<(usize, u32, u32)> {
+        let mut best: Option<(usize, u32, u32)> = None;
+        for (i, segment) in self.segments.iter().enumerate() {
+            if let Some(y) = self.fit(i, width, height) {
+                if best.map_or(true, |(_, bx, by)| y < by || (y == by && segment.x < bx)) {
+                    best = Some((i, segment.x, y));
+                }
+            }
+        }
+
+        best
+    }
+
+    fn fit(&self, index: usize, width: u32, height: u32) -> Option<u32> {
+        let x = self.segments[index].x;
+        if x + width > self.size {
+            return None;
+        }
+
+        let mut y = 0;
+        let mut covered = 0;
+        for segment in &self.segments[index..] {
+            if covered >= width {
+                break;
+            }
+            y = y.max(segment.y);
+            if y + height > self.size {
+                return None;
+            }
+            covered += segment.width;
+        }
+
+        Some(y)
+    }
+
+    fn insert(&mut self, index: usize, x: u32, y: u32, width: u32, height: u32) {
+        self.segments.insert(index, Segment {x, y: y + height, width});
+
+        // 移除或縮短被新區塊覆蓋的部分
+        let right = x + width;
+        let i = index + 1;
+        while i < self.segments.len() && self.segments[i].x < right {
+            let overlap = right - self.segments[i].x;
+            if self.segments[i].width <= overlap {
+                self.segments.remove(i);
+            } else {
+                self.segments[i].x += overlap;
+                self.segments[i].width -= overlap;
+                break;
+            }
+        }
+
+        // 合併相同高度的相鄰區段
+        let mut i = 0;
+        while i + 1 < self.segments.len() {
+            if self.segments[i].y == self.segments[i + 1].y {
+                self.segments[i].width += self.segments[i + 1].width;
+                self.segments.remove(i + 1);
+            } else {
+                i += 1;
+            }
+        }
+    }
+}
+
+// 將 sizes 中的矩形放入邊長不超過 max_size 的頁面，頁面會縮小為剛好容納所有矩形的 2 的冪次
+// 回傳的 Placement 與 sizes 的順序相同
+pub fn pack(sizes: &[(u32, u32)], max_size: u32, padding: u32) -> Result<(Vec<Page>, Vec<Placement>), PackError> {
+    if !max_size.is_power_of_two() {
+        return Err(PackError(format!("The max size of pages should be a power of two, got {}", max_size)));
+    }
+
+    // 由高至寬依序放入，可減少浪費的空間
+    let mut order: Vec<usize> = (0..sizes.len()).collect();
+    order.sort_by(|&a, &b| sizes[b].1.cmp(&sizes[a].1).then(sizes[b].0.cmp(&sizes[a].0)));
+
+    let mut skylines: Vec<Skyline> = vec![];
+    let mut placements = vec![Placement {page: 0, x: 0, y: 0}; sizes.len()];
+    for i in order {
+        let (width, height) = sizes[i];
+        if width > max_size || height > max_size {
+            return Err(PackError(format!("A graphic of {}x{} does not fit into pages of {}x{}", width, height, max_size, max_size)));
+        }
+
+        let found = skylines.iter().enumerate()
+            .find_map(|(page, skyline)| skyline.find(width + padding, height + padding).map(|found| (page, found)));
+        let (page, (index, x, y)) = match found {
+            Some(found) => found,
+            None => {
+                // 右側及下方的間距可超出頁面
+                let skyline = Skyline::new(max_size + padding);
+                let found = skyline.find(width + padding, height + padding).unwrap();
+                skylines.push(skyline);
+                (skylines.len() - 1, found)
+            },
+        };
+
+        let skyline = &mut skylines[page];
+        skyline.insert(index, x, y, width + padding, height + padding);
+        skyline.used_width = skyline.used_width.max(x + width);
+        skyline.used_height = skyline.used_height.max(y + height);
+        placements[i] = Placement {page, x, y};
+    }
+
+    let pages = skylines.iter().map(|skyline| Page {
+        width: skyline.used_width.next_power_of_two(),
+        height: skyline.used_height.next_power_of_two(),
+    }).collect();
+
+    Ok((pages, placements))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn overlaps(a: (Placement, (u32, u32)), b: (Placement, (u32, u32))) -> bool {
+        a.0.page == b.0.page &&
+        a.0.x < b.0.x + (b.1).0 && b.0.x < a.0.x + (a.1).0 &&
+        a.0.y < b.0.y + (b.1).1 && b.0.y < a.0.y + (a.1).1
+    }
+
+    #[test]
+    fn pack_without_overlap() {
+        let sizes = vec![(30, 20), (64, 64), (10, 50), (33, 7), (20, 20), (64, 10), (5, 5), (40, 40)];
+        let (pages, placements) = pack(&sizes, 128, 1).unwrap();
+
+        assert_eq!(1, pages.len());
+        assert!(pages[0].width.is_power_of_two() && pages[0].height.is_power_of_two());
+        for (i, a) in placements.iter().enumerate() {
+            assert!(a.x + sizes[i].0 <= pages[0].width && a.y + sizes[i].1 <= pages[0].height);
+            for (j, b) in placements.iter().enumerate().skip(i + 1) {
+                assert!(!overlaps((*a, sizes[i]), (*b, sizes[j])), "{:?} overlaps {:?}", a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn pack_into_multiple_pages() {
+        let (pages, placements) = pack(&[(60, 60), (60, 60), (60, 60)], 64, 0).unwrap();
+
+        assert_eq!(vec![Page {width: 64, height: 64}; 3], pages);
+        assert_eq!(vec![0, 1, 2], placements.iter().map(|p| p.page).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn pack_oversize_graphic() {
+        assert!(pack(&[(65, 1)], 64, 0).is_err());
+        assert!(pack(&[(64, 64)], 64, 1).is_ok());
+        assert!(pack(&[(1, 1)], 100, 0).is_err());
+    }
+}
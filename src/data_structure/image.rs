@@ -0,0 +1,431 @@
+use std::io;
+use serde::Serialize;
+use crate::data_structure::graphic::{GraphicInfo, GraphicData, Palette, Pixel};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Rect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+// 以色號表示的圖片，資料由上而下儲存
+#[derive(Debug, Clone, PartialEq)]
+pub struct IndexedImage {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+impl IndexedImage {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {width, height, pixels: vec![0; width as usize * height as usize]}
+    }
+
+    // data 需為已解碼的 GraphicData，圖片資料由下而上儲存
+    pub fn build(info: &GraphicInfo, data: &GraphicData) -> Result<Option<Self>, io::Error> {
+        let data = data.bytes();
+        if data.is_empty() || info.width == 0 || info.height == 0 {
+            return Ok(None);
+        }
+        if !info.valid_perimeter() || data.len() < (info.width * info.height) as usize {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Graphic data does not fit {}x{} (id: {})", info.width, info.height, info.id),
+            ));
+        }
+
+        let mut pixels = Vec::with_capacity((info.width * info.height) as usize);
+        for row in data[..(info.width * info.height) as usize].chunks_exact(info.width as usize).rev() {
+            pixels.extend_from_slice(row);
+        }
+
+        Ok(Some(Self {width: info.width, height: info.height, pixels}))
+    }
+
+    pub fn get_pixel(&self, x: u32, y: u32) -> u8 {
+        self.pixels[y as usize * self.width as usize + x as usize]
+    }
+
+    pub fn set_pixel(&mut self, x: u32, y: u32, index: u8) {
+        self.pixels[y as usize * self.width as usize + x as usize] = index;
+    }
+
+    // 不透明（色號不為 0）像素的範圍，全部透明時回傳 None
+    pub fn opaque_bounds(&self) -> Option<Rect> {
+        opaque_bounds(self.width, self.height, |x, y| self.get_pixel(x, y) != 0)
+    }
+
+    pub fn crop(&self, rect: Rect) -> Self {
+        let mut img = Self::new(rect.width, rect.height);
+        for y in 0..rect.height {
+            let start = (rect.y + y) as usize * self.width as usize + rect.x as usize;
+            let row = &self.pixels[start..start + rect.width as usize];
+            img.pixels[y as usize * rect.width as usize..(y as usize + 1) * rect.width as usize].copy_from_slice(row);
+        }
+
+        img
+    }
+
+    // 色號 0 視為透明
+    pub fn to_rgba(&self, palette: &Palette) -> RgbaImage {
+        let mut img = RgbaImage::new(self.width, self.height);
+        for (i, &index) in self.pixels.iter().enumerate() {
+            let pixel = color_of(palette, index);
+            let alpha = if index == 0 { 0x00 } else { 0xff };
+            img.pixels[i * 4..i * 4 + 4].copy_from_slice(&[pixel.r, pixel.g, pixel.b, alpha]);
+        }
+
+        img
+    }
+
+    // 8-bit 的 BMP，以調色盤作為色彩表，BMP 不支援透明色，色號 0 會以調色盤的顏色呈現
+    pub fn to_bmp<W: io::Write>(&self, palette: &Palette, mut w: W) -> Result<(), io::Error> {
+        let stride = (self.width as usize).div_ceil(4) * 4;
+        let offset = 14 + 40 + 256 * 4;
+        let size = offset + stride * self.height as usize;
+
+        let mut buf = Vec::with_capacity(size);
+        // BITMAPFILEHEADER
+        buf.extend_from_slice(b"BM");
+        buf.extend_from_slice(&(size as u32).to_le_bytes());
+        buf.extend_from_slice(&[0; 4]);
+        buf.extend_from_slice(&(offset as u32).to_le_bytes());
+        // BITMAPINFOHEADER
+        buf.extend_from_slice(&40u32.to_le_bytes());
+        buf.extend_from_slice(&(self.width as i32).to_le_bytes());
+        buf.extend_from_slice(&(self.height as i32).to_le_bytes());
+        buf.extend_from_slice(&1u16.to_le_bytes());
+        buf.extend_from_slice(&8u16.to_le_bytes());
+        buf.extend_from_slice(&0u32.to_le_bytes());
+        buf.extend_from_slice(&((stride * self.height as usize) as u32).to_le_bytes());
+        buf.extend_from_slice(&2835i32.to_le_bytes());
+        buf.extend_from_slice(&2835i32.to_le_bytes());
+        buf.extend_from_slice(&256u32.to_le_bytes());
+        buf.extend_from_slice(&0u32.to_le_bytes());
+        // 色彩表
+        for index in 0..=255 {
+            let pixel = color_of(palette, index);
+            buf.extend_from_slice(&[pixel.b, pixel.g, pixel.r, 0]);
+        }
+        // BMP 的資料由下而上儲存
+        for row in self.pixels.chunks_exact(self.width as usize).rev() {
+            buf.extend_from_slice(row);
+            buf.resize(buf.len() + stride - row.len(), 0);
+        }
+
+        w.write_all(&buf)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RgbaImage {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+impl RgbaImage {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {width, height, pixels: vec![0; width as usize * height as usize * 4]}
+    }
+
+    // data 需為已解碼的 GraphicData，色號 0 視為透明
+    pub fn build(info: &GraphicInfo, data: &GraphicData, palette: &Palette) -> Result<Option<Self>, io::Error> {
+        Ok(IndexedImage::build(info, data)?.map(|img| img.to_rgba(palette)))
+    }
+
+    pub fn get_pixel(&self, x: u32, y: u32) -> [u8; 4] {
+        let i = (y as usize * self.width as usize + x as usize) * 4;
+        [self.pixels[i], self.pixels[i + 1], self.pixels[i + 2], self.pixels[i + 3]]
+    }
+
+    pub fn set_pixel(&mut self, x: u32, y: u32, rgba: [u8; 4]) {
+        let i = (y as usize * self.width as usize + x as usize) * 4;
+        self.pixels[i..i + 4].copy_from_slice(&rgba);
+    }
+
+    // 不透明（alpha 不為 0）像素的範圍，全部透明時回傳 None
+    pub fn opaque_bounds(&self) -> Option<Rect> {
+        opaque_bounds(self.width, self.height, |x, y| self.get_pixel(x, y)[3] != 0)
+    }
+
+    pub fn crop(&self, rect: Rect) -> Self {
+        let mut img = Self::new(rect.width, rect.height);
+        img.blit(self, rect, 0, 0);
+
+        img
+    }
+
+    // 將 src 中 rect 的範圍複製到 (x, y)，超出的部分會被忽略
+    pub fn blit(&mut self, src: &RgbaImage, rect: Rect, x: u32, y: u32) {
+        let width = rect.width.min(self.width.saturating_sub(x)) as usize;
+        for row in 0..rect.height.min(self.height.saturating_sub(y)) {
+            let from = ((rect.y + row) as usize * src.width as usize + rect.x as usize) * 4;
+            let to = ((y + row) as usize * self.width as usize + x as usize) * 4;
+            self.pixels[to..to + width * 4].copy_from_slice(&src.pixels[from..from + width * 4]);
+        }
+    }
+}
+
+// 以共同原點繪製圖片的畫布，每張圖片依 GraphicInfo 的偏移量放置，同一角色的各個影格不會跳動
+#[derive(Debug, Clone, PartialEq)]
+pub struct Canvas {
+    pub image: RgbaImage,
+    pub origin_x: i32,
+    pub origin_y: i32,
+}
+
+impl Canvas {
+    pub fn new(width: u32, height: u32, origin_x: i32, origin_y: i32) -> Self {
+        Self {image: RgbaImage::new(width, height), origin_x, origin_y}
+    }
+
+    // 原點位於畫布中央
+    pub fn centered(width: u32, height: u32) -> Self {
+        Self::new(width, height, (width / 2) as i32, (height / 2) as i32)
+    }
+
+    // 透明的像素不會覆蓋畫布，超出畫布的部分會被裁切；以 i64 計算位置，損毀的偏移量不會溢位
+    pub fn draw(&mut self, info: &GraphicInfo, image: &RgbaImage) {
+        let (left, top) = self.anchor(info);
+        for y in 0..image.height {
+            for x in 0..image.width {
+                let rgba = image.get_pixel(x, y);
+                if rgba[3] != 0 {
+                    self.put(left + x as i64, top + y as i64, rgba);
+                }
+            }
+        }
+    }
+
+    // 圖片的外框，用於除錯
+    pub fn draw_bounds(&mut self, info: &GraphicInfo, rgba: [u8; 4]) {
+        if info.width == 0 || info.height == 0 {
+            return;
+        }
+
+        let (left, top) = self.anchor(info);
+        let (right, bottom) = (left + info.width as i64 - 1, top + info.height as i64 - 1);
+        // 只走訪畫布內的範圍
+        let (width, height) = (self.image.width as i64, self.image.height as i64);
+        for x in left.max(0)..=right.min(width - 1) {
+            self.put(x, top, rgba);
+            self.put(x, bottom, rgba);
+        }
+        for y in top.max(0)..=bottom.min(height - 1) {
+            self.put(left, y, rgba);
+            self.put(right, y, rgba);
+        }
+    }
+
+    // 原點的十字線，用於除錯
+    pub fn draw_crosshair(&mut self, radius: i32, rgba: [u8; 4]) {
+        let (x, y) = (self.origin_x as i64, self.origin_y as i64);
+        for d in -radius as i64..=radius as i64 {
+            self.put(x + d, y, rgba);
+            self.put(x, y + d, rgba);
+        }
+    }
+
+    fn anchor(&self, info: &GraphicInfo) -> (i64, i64) {
+        (self.origin_x as i64 + info.offset_x as i64, self.origin_y as i64 + info.offset_y as i64)
+    }
+
+    fn put(&mut self, x: i64, y: i64, rgba: [u8; 4]) {
+        if x >= 0 && y >= 0 && x < self.image.width as i64 && y < self.image.height as i64 {
+            self.image.set_pixel(x as u32, y as u32, rgba);
+        }
+    }
+}
+
+fn opaque_bounds<F: Fn(u32, u32) -> bool>(width: u32, height: u32, opaque: F) -> Option<Rect> {
+    let (mut left, mut top, mut right, mut bottom) = (width, height, 0, 0);
+    for y in 0..height {
+        for x in 0..width {
+            if opaque(x, y) {
+                left = left.min(x);
+                top = top.min(y);
+                right = right.max(x + 1);
+                bottom = bottom.max(y + 1);
+            }
+        }
+    }
+
+    if left >= right || top >= bottom {
+        return None;
+    }
+
+    Some(Rect {x: left, y: top, width: right - left, height: bottom - top})
+}
+
+fn color_of(palette: &Palette, index: u8) -> Pixel {
+    palette.colors().get(index as usize).copied().unwrap_or(Pixel {b: 0, g: 0, r: 0})
+}
+
+#[cfg(feature = "bmp")]
+impl RgbaImage {
+    pub fn to_bmp(&self) -> bmp::Image {
+        let mut img = bmp::Image::new(self.width, self.height);
+        for (x, y) in img.coordinates() {
+            let [r, g, b, _] = self.get_pixel(x, y);
+            img.set_pixel(x, y, bmp::Pixel::new(r, g, b));
+        }
+
+        img
+    }
+
+    // BMP 沒有透明度，全部視為不透明
+    pub fn from_bmp(bmp: &bmp::Image) -> Self {
+        let mut img = Self::new(bmp.get_width(), bmp.get_height());
+        for (x, y) in bmp.coordinates() {
+            let pixel = bmp.get_pixel(x, y);
+            img.set_pixel(x, y, [pixel.r, pixel.g, pixel.b, 0xff]);
+        }
+
+        img
+    }
+}
+
+#[cfg(feature = "png")]
+impl IndexedImage {
+    // 以調色盤作為 PLTE，並將色號 0 標記為透明
+    pub fn to_png<W: io::Write>(&self, palette: &Palette, w: W) -> Result<(), png::EncodingError> {
+        let mut plte = Vec::with_capacity(256 * 3);
+        for index in 0..=255 {
+            let pixel = color_of(palette, index);
+            plte.extend_from_slice(&[pixel.r, pixel.g, pixel.b]);
+        }
+
+        let mut encoder = png::Encoder::new(w, self.width, self.height);
+        encoder.set_color(png::ColorType::Indexed);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.set_palette(plte);
+        encoder.set_trns(vec![0x00]);
+        encoder.write_header()?.write_image_data(&self.pixels)
+    }
+}
+
+#[cfg(feature = "png")]
+impl RgbaImage {
+    pub fn to_png<W: io::Write>(&self, w: W) -> Result<(), png::EncodingError> {
+        let mut encoder = png::Encoder::new(w, self.width, self.height);
+        encoder.set_color(png::ColorType::RGBA);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.write_header()?.write_image_data(&self.pixels)
+    }
+
+    pub fn from_png<R: io::Read>(r: R) -> Result<Self, png::DecodingError> {
+        let mut decoder = png::Decoder::new(r);
+        decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+        let (info, mut reader) = decoder.read_info()?;
+        let mut buf = vec![0; info.buffer_size()];
+        reader.next_frame(&mut buf)?;
+
+        let mut img = Self::new(info.width, info.height);
+        for (y, line) in buf.chunks_exact(info.line_size).enumerate() {
+            for x in 0..info.width as usize {
+                let rgba = match info.color_type {
+                    png::ColorType::Grayscale => [line[x], line[x], line[x], 0xff],
+                    png::ColorType::GrayscaleAlpha => [line[x * 2], line[x * 2], line[x * 2], line[x * 2 + 1]],
+                    png::ColorType::RGB => [line[x * 3], line[x * 3 + 1], line[x * 3 + 2], 0xff],
+                    png::ColorType::RGBA => [line[x * 4], line[x * 4 + 1], line[x * 4 + 2], line[x * 4 + 3]],
+                    png::ColorType::Indexed => return Err(png::DecodingError::Format("Indexed PNG is not expanded".into())),
+                };
+                img.set_pixel(x as u32, y as u32, rgba);
+            }
+        }
+
+        Ok(img)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn graphic_info(width: u32, height: u32) -> GraphicInfo {
+        GraphicInfo {
+            id: 0, address: 0, length: 0, offset_x: 0, offset_y: 0, width, height, tile_east: 1, tile_south: 1, access: 1, unknown: [0, 0, 0, 0, 0], map: 0
+        }
+    }
+
+    #[test]
+    fn build_indexed_image_from_bottom_up_data() {
+        let data = GraphicData::from(vec![1, 2, 3, 4, 5, 6]);
+        let img = IndexedImage::build(&graphic_info(3, 2), &data).unwrap().unwrap();
+
+        assert_eq!(vec![4, 5, 6, 1, 2, 3], img.pixels);
+    }
+
+    #[test]
+    fn build_indexed_image_with_short_data() {
+        let data = GraphicData::from(vec![1, 2, 3]);
+
+        assert!(IndexedImage::build(&graphic_info(3, 2), &data).is_err());
+    }
+
+    #[test]
+    fn indexed_image_to_rgba() {
+        let img = IndexedImage {width: 2, height: 1, pixels: vec![0, 16]};
+        let rgba = img.to_rgba(&Palette::default());
+
+        assert_eq!(vec![0x00, 0x00, 0x00, 0x00, 0xa4, 0xf7, 0x85, 0xff], rgba.pixels);
+    }
+
+    #[test]
+    fn trim_transparent_borders() {
+        let img = IndexedImage {width: 4, height: 3, pixels: vec![0, 0, 0, 0, 0, 1, 2, 0, 0, 0, 3, 0]};
+        let rect = img.opaque_bounds().unwrap();
+
+        assert_eq!(Rect {x: 1, y: 1, width: 2, height: 2}, rect);
+        assert_eq!(vec![1, 2, 0, 3], img.crop(rect).pixels);
+        assert_eq!(img.to_rgba(&Palette::default()).opaque_bounds(), Some(rect));
+        assert_eq!(None, IndexedImage::new(2, 2).opaque_bounds());
+    }
+
+    #[test]
+    fn draw_on_canvas_with_offsets() {
+        let mut info = graphic_info(2, 2);
+        info.offset_x = -1;
+        info.offset_y = -2;
+        let mut image = RgbaImage::new(2, 2);
+        image.set_pixel(0, 0, [1, 2, 3, 0xff]);
+        image.set_pixel(1, 1, [4, 5, 6, 0xff]);
+
+        let mut canvas = Canvas::new(4, 4, 1, 1);
+        canvas.draw(&info, &image);
+
+        // (0, 0) 超出畫布而被裁切
+        assert_eq!([0, 0, 0, 0], canvas.image.get_pixel(0, 0));
+        assert_eq!([4, 5, 6, 0xff], canvas.image.get_pixel(1, 0));
+        assert_eq!(Some(Rect {x: 1, y: 0, width: 1, height: 1}), canvas.image.opaque_bounds());
+    }
+
+    #[test]
+    fn draw_with_extreme_offsets() {
+        let mut info = graphic_info(u32::MAX, 2);
+        info.offset_x = i32::MAX;
+        info.offset_y = i32::MIN;
+        let mut image = RgbaImage::new(2, 2);
+        image.set_pixel(0, 0, [1, 2, 3, 0xff]);
+
+        let mut canvas = Canvas::new(4, 4, i32::MAX, i32::MAX);
+        canvas.draw(&info, &image);
+        canvas.draw_bounds(&info, [0xff; 4]);
+        canvas.draw_crosshair(4, [0xff; 4]);
+
+        assert_eq!(None, canvas.image.opaque_bounds());
+    }
+
+    #[test]
+    fn indexed_image_to_bmp() {
+        let img = IndexedImage {width: 3, height: 2, pixels: vec![1, 2, 3, 4, 5, 6]};
+        let mut bmp = vec![];
+        img.to_bmp(&Palette::default(), &mut bmp).unwrap();
+
+        assert_eq!(14 + 40 + 1024 + 8, bmp.len());
+        assert_eq!(&[4, 5, 6, 0, 1, 2, 3, 0], &bmp[1078..]);
+    }
+}
@@ -1 +1,6 @@
-pub mod graphic;
\ No newline at end of file
+pub mod graphic;
+pub mod image;
+pub mod atlas;
+pub mod scale;
+pub mod terminal;
+pub mod phash;
\ No newline at end of file
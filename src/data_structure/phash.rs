@@ -0,0 +1,119 @@
+use serde::{Serialize, Deserialize};
+use crate::data_structure::image::RgbaImage;
+
+// 感知雜湊：縮小為 8x8 的平均雜湊（aHash）及 9x8 的差異雜湊（dHash），
+// 不受縮放及些微色差影響，以漢明距離比較相似程度
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImageHash {
+    pub average: u64,
+    pub difference: u64,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl ImageHash {
+    // 先去除透明邊緣，全部透明時回傳 None；透明的像素視為黑色
+    pub fn new(image: &RgbaImage) -> Option<Self> {
+        let bounds = image.opaque_bounds()?;
+        let image = image.crop(bounds);
+
+        let average_grid = shrink(&image, 8, 8);
+        let mean = average_grid.iter().sum::<u32>() / 64;
+        let average = average_grid.iter().enumerate()
+            .filter(|(_, &gray)| gray > mean)
+            .fold(0u64, |hash, (i, _)| hash | 1 << i);
+
+        let difference_grid = shrink(&image, 9, 8);
+        let mut difference = 0u64;
+        for y in 0..8 {
+            for x in 0..8 {
+                if difference_grid[y * 9 + x] < difference_grid[y * 9 + x + 1] {
+                    difference |= 1 << (y * 8 + x);
+                }
+            }
+        }
+
+        Some(Self {average, difference, width: bounds.width, height: bounds.height})
+    }
+
+    // 0 ~ 128，越小越相似
+    pub fn distance(&self, other: &ImageHash) -> u32 {
+        (self.average ^ other.average).count_ones() + (self.difference ^ other.difference).count_ones()
+    }
+
+    // 長寬比的差異，用於距離相同時排序
+    pub fn aspect_difference(&self, other: &ImageHash) -> f64 {
+        let ratio = |hash: &ImageHash| (hash.width as f64 / hash.height as f64).ln();
+
+        (ratio(self) - ratio(other)).abs()
+    }
+}
+
+// 以區域平均縮小為 columns x rows 的灰階，來源小於目標時重複取樣
+fn shrink(image: &RgbaImage, columns: u32, rows: u32) -> Vec<u32> {
+    let mut ret = Vec::with_capacity((columns * rows) as usize);
+    for row in 0..rows {
+        let (top, bottom) = span(row, rows, image.height);
+        for column in 0..columns {
+            let (left, right) = span(column, columns, image.width);
+            let mut sum = 0;
+            for y in top..bottom {
+                for x in left..right {
+                    let [r, g, b, a] = image.get_pixel(x, y);
+                    if a != 0 {
+                        sum += (r as u32 * 299 + g as u32 * 587 + b as u32 * 114) / 1000;
+                    }
+                }
+            }
+            ret.push(sum / ((bottom - top) * (right - left)));
+        }
+    }
+
+    ret
+}
+
+fn span(index: u32, count: u32, size: u32) -> (u32, u32) {
+    let start = (index * size / count).min(size - 1);
+    let end = ((index + 1) * size / count).max(start + 1);
+
+    (start, end)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn gradient(width: u32, height: u32, flip: bool) -> RgbaImage {
+        let mut img = RgbaImage::new(width, height);
+        for y in 0..height {
+            for x in 0..width {
+                let v = ((x * 7 + y * 3) * 255 / (width * 7 + height * 3)) as u8;
+                let v = if flip { 255 - v } else { v };
+                img.set_pixel(x, y, [v, v / 2, 255 - v, 255]);
+            }
+        }
+
+        img
+    }
+
+    #[test]
+    fn similar_images_are_close() {
+        let original = ImageHash::new(&gradient(32, 24, false)).unwrap();
+        let scaled = ImageHash::new(&gradient(64, 48, false)).unwrap();
+        let flipped = ImageHash::new(&gradient(32, 24, true)).unwrap();
+
+        assert!(original.distance(&scaled) <= 8, "{}", original.distance(&scaled));
+        assert!(original.distance(&flipped) > 64, "{}", original.distance(&flipped));
+        assert_eq!(0, original.distance(&original));
+    }
+
+    #[test]
+    fn hash_ignores_transparent_borders() {
+        let mut padded = RgbaImage::new(40, 30);
+        let img = gradient(32, 24, false);
+        padded.blit(&img, crate::data_structure::image::Rect {x: 0, y: 0, width: 32, height: 24}, 4, 3);
+
+        assert_eq!(ImageHash::new(&img), ImageHash::new(&padded));
+        assert_eq!(None, ImageHash::new(&RgbaImage::new(4, 4)));
+    }
+}
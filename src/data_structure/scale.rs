@@ -0,0 +1,298 @@
+use std::fmt;
+use std::str::FromStr;
+use crate::data_structure::image::{IndexedImage, RgbaImage};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ScaleFilter {
+    Nearest,
+    // EPX / AdvMAME2x，倍率需為 2 的冪次
+    Scale2x,
+    // AdvMAME3x，倍率需為 3 的冪次
+    Scale3x,
+}
+
+impl FromStr for ScaleFilter {
+    type Err = ScaleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "nearest" => Ok(ScaleFilter::Nearest),
+            "scale2x" => Ok(ScaleFilter::Scale2x),
+            "scale3x" => Ok(ScaleFilter::Scale3x),
+            _ => Err(ScaleError(format!("Unknown scale filter: {}", s))),
+        }
+    }
+}
+
+impl fmt::Display for ScaleFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScaleFilter::Nearest => write!(f, "nearest"),
+            ScaleFilter::Scale2x => write!(f, "scale2x"),
+            ScaleFilter::Scale3x => write!(f, "scale3x"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ScaleError(String);
+
+impl fmt::Display for ScaleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for ScaleError {}
+
+// 倍率上限，避免放大後的圖片耗盡記憶體
+pub const MAX_FACTOR: u32 = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Scale {
+    factor: u32,
+    filter: ScaleFilter,
+}
+
+impl Scale {
+    pub fn new(factor: u32, filter: ScaleFilter) -> Result<Self, ScaleError> {
+        if factor > MAX_FACTOR {
+            return Err(ScaleError(format!("Scale factor should not be greater than {}: {}", MAX_FACTOR, factor)));
+        }
+        let valid = match filter {
+            ScaleFilter::Nearest => factor > 0,
+            ScaleFilter::Scale2x => factor.is_power_of_two(),
+            ScaleFilter::Scale3x => {
+                let mut f = factor;
+                while f > 1 && f % 3 == 0 {
+                    f /= 3;
+                }
+                f == 1
+            },
+        };
+        if !valid {
+            return Err(ScaleError(format!("Cannot scale by {} with {}", factor, filter)));
+        }
+
+        Ok(Self {factor, filter})
+    }
+
+    pub fn factor(&self) -> u32 {
+        self.factor
+    }
+
+    pub fn filter(&self) -> ScaleFilter {
+        self.filter
+    }
+
+    // 放大後的大小，溢位時回傳錯誤
+    pub fn size(&self, width: u32, height: u32, bytes_per_pixel: usize) -> Result<(u32, u32), ScaleError> {
+        let too_large = || ScaleError(format!("Cannot scale {}x{} by {}, the result is too large", width, height, self.factor));
+        let scaled_width = width.checked_mul(self.factor).ok_or_else(too_large)?;
+        let scaled_height = height.checked_mul(self.factor).ok_or_else(too_large)?;
+        (scaled_width as usize).checked_mul(scaled_height as usize)
+            .and_then(|pixels| pixels.checked_mul(bytes_per_pixel))
+            .filter(|&bytes| bytes <= isize::MAX as usize)
+            .ok_or_else(too_large)?;
+
+        Ok((scaled_width, scaled_height))
+    }
+
+    fn apply<T: Copy + PartialEq>(&self, pixels: Vec<T>, width: u32, height: u32) -> Vec<T> {
+        let (mut pixels, mut width, mut height) = (pixels, width as usize, height as usize);
+        match self.filter {
+            ScaleFilter::Nearest => return nearest(&pixels, width, height, self.factor as usize),
+            // 重複套用直到達到倍率
+            ScaleFilter::Scale2x => {
+                for _ in 0..self.factor.trailing_zeros() {
+                    pixels = scale2x(&pixels, width, height);
+                    width *= 2;
+                    height *= 2;
+                }
+            },
+            ScaleFilter::Scale3x => {
+                let mut factor = self.factor;
+                while factor > 1 {
+                    pixels = scale3x(&pixels, width, height);
+                    width *= 3;
+                    height *= 3;
+                    factor /= 3;
+                }
+            },
+        }
+
+        pixels
+    }
+}
+
+impl Default for Scale {
+    fn default() -> Self {
+        Self {factor: 1, filter: ScaleFilter::Nearest}
+    }
+}
+
+impl IndexedImage {
+    pub fn scaled(&self, scale: Scale) -> Result<Self, ScaleError> {
+        let (width, height) = scale.size(self.width, self.height, 1)?;
+
+        Ok(Self {
+            width,
+            height,
+            pixels: scale.apply(self.pixels.clone(), self.width, self.height),
+        })
+    }
+}
+
+impl RgbaImage {
+    pub fn scaled(&self, scale: Scale) -> Result<Self, ScaleError> {
+        let (width, height) = scale.size(self.width, self.height, 4)?;
+        let pixels: Vec<[u8; 4]> = self.pixels.chunks_exact(4).map(|p| [p[0], p[1], p[2], p[3]]).collect();
+
+        Ok(Self {
+            width,
+            height,
+            pixels: scale.apply(pixels, self.width, self.height).concat(),
+        })
+    }
+}
+
+fn nearest<T: Copy>(pixels: &[T], width: usize, height: usize, factor: usize) -> Vec<T> {
+    let mut ret = Vec::with_capacity(pixels.len() * factor * factor);
+    for y in 0..height * factor {
+        for x in 0..width * factor {
+            ret.push(pixels[(y / factor) * width + x / factor]);
+        }
+    }
+
+    ret
+}
+
+// 超出邊界時取最接近的像素
+fn neighbor<T: Copy>(pixels: &[T], width: usize, height: usize, x: usize, y: usize, dx: isize, dy: isize) -> T {
+    let x = (x as isize + dx).clamp(0, width as isize - 1) as usize;
+    let y = (y as isize + dy).clamp(0, height as isize - 1) as usize;
+
+    pixels[y * width + x]
+}
+
+fn scale2x<T: Copy + PartialEq>(pixels: &[T], width: usize, height: usize) -> Vec<T> {
+    if pixels.is_empty() {
+        return vec![];
+    }
+    let mut ret = vec![pixels[0]; pixels.len() * 4];
+
+    for y in 0..height {
+        for x in 0..width {
+            let at = |dx, dy| neighbor(pixels, width, height, x, y, dx, dy);
+            let (p, a, b, c, d) = (at(0, 0), at(0, -1), at(1, 0), at(-1, 0), at(0, 1));
+
+            let out = [
+                if c == a && c != d && a != b { a } else { p },
+                if a == b && a != c && b != d { b } else { p },
+                if d == c && d != b && c != a { c } else { p },
+                if b == d && b != a && d != c { d } else { p },
+            ];
+            for (i, &pixel) in out.iter().enumerate() {
+                ret[(y * 2 + i / 2) * width * 2 + x * 2 + i % 2] = pixel;
+            }
+        }
+    }
+
+    ret
+}
+
+fn scale3x<T: Copy + PartialEq>(pixels: &[T], width: usize, height: usize) -> Vec<T> {
+    if pixels.is_empty() {
+        return vec![];
+    }
+    let mut ret = vec![pixels[0]; pixels.len() * 9];
+
+    for y in 0..height {
+        for x in 0..width {
+            let at = |dx, dy| neighbor(pixels, width, height, x, y, dx, dy);
+            let (a, b, c) = (at(-1, -1), at(0, -1), at(1, -1));
+            let (d, e, f) = (at(-1, 0), at(0, 0), at(1, 0));
+            let (g, h, i) = (at(-1, 1), at(0, 1), at(1, 1));
+
+            let out = [
+                if d == b && d != h && b != f { d } else { e },
+                if (d == b && d != h && b != f && e != c) || (b == f && b != d && f != h && e != a) { b } else { e },
+                if b == f && b != d && f != h { f } else { e },
+                if (d == b && d != h && b != f && e != g) || (d == h && d != b && h != f && e != a) { d } else { e },
+                e,
+                if (b == f && b != d && f != h && e != i) || (h == f && h != d && f != b && e != c) { f } else { e },
+                if d == h && d != b && h != f { d } else { e },
+                if (h == f && h != d && f != b && e != g) || (d == h && d != b && h != f && e != i) { h } else { e },
+                if h == f && h != d && f != b { f } else { e },
+            ];
+            for (n, &pixel) in out.iter().enumerate() {
+                ret[(y * 3 + n / 3) * width * 3 + x * 3 + n % 3] = pixel;
+            }
+        }
+    }
+
+    ret
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn validate_scale() {
+        assert!(Scale::new(3, ScaleFilter::Nearest).is_ok());
+        assert!(Scale::new(0, ScaleFilter::Nearest).is_err());
+        assert!(Scale::new(4, ScaleFilter::Scale2x).is_ok());
+        assert!(Scale::new(3, ScaleFilter::Scale2x).is_err());
+        assert!(Scale::new(9, ScaleFilter::Scale3x).is_ok());
+        assert!(Scale::new(6, ScaleFilter::Scale3x).is_err());
+        assert!(Scale::new(MAX_FACTOR, ScaleFilter::Nearest).is_ok());
+        assert!(Scale::new(MAX_FACTOR + 1, ScaleFilter::Nearest).is_err());
+        assert!(Scale::new(27, ScaleFilter::Scale3x).is_err());
+    }
+
+    #[test]
+    fn reject_overflowing_size() {
+        let scale = Scale::new(16, ScaleFilter::Nearest).unwrap();
+
+        assert_eq!((64, 32), scale.size(4, 2, 4).unwrap());
+        assert!(scale.size(u32::MAX / 2, 1, 1).is_err());
+        assert!(scale.size(0x1000_0000, 0x1000_0000, 4).is_err());
+    }
+
+    #[test]
+    fn nearest_neighbor() {
+        let img = IndexedImage {width: 2, height: 1, pixels: vec![1, 2]};
+        let scaled = img.scaled(Scale::new(2, ScaleFilter::Nearest).unwrap()).unwrap();
+
+        assert_eq!((4, 2), (scaled.width, scaled.height));
+        assert_eq!(vec![1, 1, 2, 2, 1, 1, 2, 2], scaled.pixels);
+    }
+
+    #[test]
+    fn scale2x_diagonal() {
+        // 1 0
+        // 0 1
+        let img = IndexedImage {width: 2, height: 2, pixels: vec![1, 0, 0, 1]};
+        let scaled = img.scaled(Scale::new(2, ScaleFilter::Scale2x).unwrap()).unwrap();
+
+        assert_eq!(vec![
+            1, 1, 0, 0,
+            1, 0, 1, 0,
+            0, 1, 0, 1,
+            0, 0, 1, 1,
+        ], scaled.pixels);
+    }
+
+    #[test]
+    fn scale_rgba_keeps_solid_color() {
+        let mut img = RgbaImage::new(2, 2);
+        for (x, y) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+            img.set_pixel(x, y, [1, 2, 3, 4]);
+        }
+        let scaled = img.scaled(Scale::new(3, ScaleFilter::Scale3x).unwrap()).unwrap();
+
+        assert_eq!((6, 6), (scaled.width, scaled.height));
+        assert!(scaled.pixels.chunks_exact(4).all(|p| p == [1, 2, 3, 4]));
+    }
+}
@@ -0,0 +1,131 @@
+use std::env;
+use std::fmt::Write;
+use crate::data_structure::image::RgbaImage;
+
+const RESET: &str = "\x1b[0m";
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ColorMode {
+    TrueColor,
+    // xterm 的 256 色：6x6x6 色塊及 24 階灰階
+    Ansi256,
+}
+
+impl ColorMode {
+    // 依 COLORTERM 判斷終端機是否支援 24 位元色彩
+    pub fn detect() -> Self {
+        match env::var("COLORTERM") {
+            Ok(value) if value == "truecolor" || value == "24bit" => ColorMode::TrueColor,
+            _ => ColorMode::Ansi256,
+        }
+    }
+
+    // layer 為 38（前景）或 48（背景）
+    fn escape(&self, layer: u8, [r, g, b]: [u8; 3]) -> String {
+        match self {
+            ColorMode::TrueColor => format!("\x1b[{};2;{};{};{}m", layer, r, g, b),
+            ColorMode::Ansi256 => format!("\x1b[{};5;{}m", layer, ansi256([r, g, b])),
+        }
+    }
+}
+
+fn ansi256([r, g, b]: [u8; 3]) -> u8 {
+    let level = |v: u8| match v {
+        0..=47 => 0,
+        48..=114 => 1,
+        _ => (v - 35) / 40,
+    };
+    let distance = |[r2, g2, b2]: [u8; 3]| {
+        let d = |a: u8, b: u8| (a as i32 - b as i32).pow(2);
+        d(r, r2) + d(g, g2) + d(b, b2)
+    };
+
+    let (cr, cg, cb) = (level(r), level(g), level(b));
+    let cube = [CUBE_LEVELS[cr as usize], CUBE_LEVELS[cg as usize], CUBE_LEVELS[cb as usize]];
+    let average = ((r as u32 + g as u32 + b as u32) / 3) as u8;
+    let step = if average > 238 { 23 } else { average.saturating_sub(3) / 10 };
+    let gray = 8 + step * 10;
+
+    if distance([gray, gray, gray]) < distance(cube) {
+        232 + step
+    } else {
+        16 + cr * 36 + cg * 6 + cb
+    }
+}
+
+impl RgbaImage {
+    // 以半格字元（▀ ▄）在終端機上顯示，每個字元表示上下兩個像素，透明處保留終端機的背景色
+    // 圖片超過 max_columns x max_rows 個字元時，以整數倍率縮小
+    pub fn to_half_blocks(&self, max_columns: u32, max_rows: u32, mode: ColorMode) -> Vec<String> {
+        if self.width == 0 || self.height == 0 || max_columns == 0 || max_rows == 0 {
+            return vec![];
+        }
+        let factor = self.width.div_ceil(max_columns).max(self.height.div_ceil(max_rows * 2)).max(1);
+        let (width, height) = (self.width.div_ceil(factor), self.height.div_ceil(factor));
+        let pixel = |x: u32, y: u32| -> Option<[u8; 3]> {
+            if y >= height {
+                return None;
+            }
+            let [r, g, b, a] = self.get_pixel(x * factor, y * factor);
+            if a < 0x80 { None } else { Some([r, g, b]) }
+        };
+
+        let mut lines = Vec::with_capacity(height.div_ceil(2) as usize);
+        for row in 0..height.div_ceil(2) {
+            let mut line = String::new();
+            for x in 0..width {
+                match (pixel(x, row * 2), pixel(x, row * 2 + 1)) {
+                    (Some(top), Some(bottom)) => write!(line, "{}{}▀", mode.escape(38, top), mode.escape(48, bottom)),
+                    (Some(top), None) => write!(line, "{}{}▀", RESET, mode.escape(38, top)),
+                    (None, Some(bottom)) => write!(line, "{}{}▄", RESET, mode.escape(38, bottom)),
+                    (None, None) => write!(line, "{} ", RESET),
+                }.unwrap();
+            }
+            line.push_str(RESET);
+            lines.push(line);
+        }
+
+        lines
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn render_half_blocks() {
+        let mut img = RgbaImage::new(2, 3);
+        img.set_pixel(0, 0, [255, 0, 0, 255]);
+        img.set_pixel(0, 1, [0, 0, 255, 255]);
+        img.set_pixel(1, 1, [0, 255, 0, 255]);
+        let lines = img.to_half_blocks(80, 24, ColorMode::TrueColor);
+
+        assert_eq!(vec![
+            "\x1b[38;2;255;0;0m\x1b[48;2;0;0;255m▀\x1b[0m\x1b[38;2;0;255;0m▄\x1b[0m",
+            "\x1b[0m \x1b[0m \x1b[0m",
+        ], lines);
+    }
+
+    #[test]
+    fn nearest_ansi256_color() {
+        assert_eq!(16, ansi256([0, 0, 0]));
+        assert_eq!(196, ansi256([255, 0, 0]));
+        assert_eq!(231, ansi256([255, 255, 255]));
+        assert_eq!(244, ansi256([128, 128, 128]));
+        assert_eq!(vec!["\x1b[0m\x1b[38;5;21m▀\x1b[0m"], {
+            let mut img = RgbaImage::new(1, 1);
+            img.set_pixel(0, 0, [0, 0, 255, 255]);
+            img.to_half_blocks(1, 1, ColorMode::Ansi256)
+        });
+    }
+
+    #[test]
+    fn shrink_to_fit() {
+        let img = RgbaImage::new(100, 40);
+
+        assert_eq!(4, img.to_half_blocks(20, 10, ColorMode::TrueColor).len());
+        assert_eq!(20, img.to_half_blocks(100, 20, ColorMode::Ansi256).len());
+    }
+}
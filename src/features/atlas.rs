@@ -0,0 +1,205 @@
+use std::collections::BTreeMap;
+use log::{debug, info, trace, warn};
+use serde::Serialize;
+use crate::data_structure::atlas::pack;
+use crate::data_structure::graphic::GraphicInfo;
+use crate::data_structure::image::{Rect, RgbaImage};
+use crate::features::ArgParse;
+use crate::features::sink::Sink;
+use crate::resource::archive::Archive;
+
+#[derive(Debug, Serialize)]
+struct Size {
+    w: u32,
+    h: u32,
+}
+
+#[derive(Debug, Serialize)]
+struct Frame {
+    x: u32,
+    y: u32,
+    w: u32,
+    h: u32,
+}
+
+#[derive(Debug, Serialize)]
+struct Point<T> {
+    x: T,
+    y: T,
+}
+
+// TexturePacker 的 JSON (Hash) 格式，另外加上 offset（錨點）及 map
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SpriteFrame {
+    frame: Frame,
+    rotated: bool,
+    trimmed: bool,
+    sprite_source_size: Frame,
+    source_size: Size,
+    pivot: Point<f64>,
+    offset: Point<i32>,
+    map: u32,
+}
+
+#[derive(Debug, Serialize)]
+struct Meta<'a> {
+    app: &'a str,
+    version: &'a str,
+    image: String,
+    format: &'a str,
+    size: Size,
+    scale: &'a str,
+}
+
+#[derive(Debug, Serialize)]
+struct Descriptor<'a> {
+    frames: BTreeMap<String, SpriteFrame>,
+    meta: Meta<'a>,
+}
+
+struct Sprite<'a> {
+    info: &'a GraphicInfo,
+    image: RgbaImage,
+    // 去除透明邊緣後，在原圖中的範圍
+    bounds: Rect,
+}
+
+pub fn pack_atlas(
+    args: &clap::ArgMatches,
+    archive: &Archive,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    info!("Parsing sub command arguments");
+    let result = ArgParse::parse(args)?;
+    info!("Parsed sub command arguments");
+    debug!("{:?}", result);
+
+    let output = result.output.ok_or("No output chosen, please specify --output")?;
+    let palette = match result.palette_name {
+        Some(name) => archive.find_palette(name).ok_or_else(|| format!("Palette not found: {}", name))?,
+        None => archive.palette(),
+    };
+
+    let mut sprites = vec![];
+    for info in select_graphics(&result, archive)? {
+        trace!("Graphic ID: {}", info.id);
+        let image = match archive.render(info.id, palette)? {
+            Some(image) => image,
+            None => {
+                warn!("Empty Graphic Data (id: {})", info.id);
+                continue;
+            },
+        };
+        match image.opaque_bounds() {
+            Some(bounds) => sprites.push(Sprite {info, image: image.crop(bounds), bounds}),
+            None => warn!("Fully transparent graphic (id: {})", info.id),
+        }
+    }
+    if sprites.is_empty() {
+        return Err("No graphics selected".into());
+    }
+
+    let sizes: Vec<(u32, u32)> = sprites.iter().map(|s| (s.bounds.width, s.bounds.height)).collect();
+    let (pages, placements) = pack(&sizes, result.max_size.unwrap(), result.padding.unwrap())?;
+    info!("Packed {} graphics into {} pages", sprites.len(), pages.len());
+
+    let name = result.name.unwrap();
+    let mut sink = Sink::directory(output);
+    for (page, size) in pages.iter().enumerate() {
+        let image_name = format!("{}_{}.png", name, page);
+        let mut image = RgbaImage::new(size.width, size.height);
+        let mut frames = BTreeMap::new();
+
+        for (sprite, placement) in sprites.iter().zip(&placements).filter(|(_, p)| p.page == page) {
+            let (info, bounds) = (sprite.info, sprite.bounds);
+            let whole = Rect {x: 0, y: 0, width: bounds.width, height: bounds.height};
+            image.blit(&sprite.image, whole, placement.x, placement.y);
+
+            frames.insert(info.id.to_string(), SpriteFrame {
+                frame: Frame {x: placement.x, y: placement.y, w: bounds.width, h: bounds.height},
+                rotated: false,
+                trimmed: bounds.width != info.width || bounds.height != info.height,
+                sprite_source_size: Frame {x: bounds.x, y: bounds.y, w: bounds.width, h: bounds.height},
+                source_size: Size {w: info.width, h: info.height},
+                // 錨點位於原圖的 (-offset_x, -offset_y)
+                pivot: Point {
+                    x: -info.offset_x as f64 / info.width as f64,
+                    y: -info.offset_y as f64 / info.height as f64,
+                },
+                offset: Point {x: info.offset_x, y: info.offset_y},
+                map: info.map,
+            });
+        }
+
+        let mut png = vec![];
+        image.to_png(&mut png)?;
+        sink.write(&image_name, &png)?;
+
+        let descriptor = Descriptor {
+            frames,
+            meta: Meta {
+                app: env!("CARGO_PKG_NAME"),
+                version: env!("CARGO_PKG_VERSION"),
+                image: image_name,
+                format: "RGBA8888",
+                size: Size {w: size.width, h: size.height},
+                scale: "1",
+            },
+        };
+        sink.write(&format!("{}_{}.json", name, page), &serde_json::to_vec_pretty(&descriptor)?)?;
+    }
+    sink.finish()?;
+
+    Ok(())
+}
+
+// 依動畫的影格清單、id 範圍或地圖編號選擇圖片
+pub(crate) fn select_infos<'a>(
+    args: &ArgParse,
+    archive: &'a Archive,
+) -> Result<Vec<&'a GraphicInfo>, Box<dyn std::error::Error + Send + Sync>> {
+    let mut ret: Vec<&GraphicInfo> = vec![];
+
+    if let Some(frames) = &args.frames {
+        for &id in frames {
+            let info = archive.info(id).ok_or_else(|| format!("Graphic not found (id: {})", id))?;
+            // 同一張圖片可能在動畫中重複出現
+            if !ret.iter().any(|gi| gi.id == id) {
+                ret.push(info);
+            }
+        }
+    } else if args.range.is_some() || args.map.is_some() {
+        for info in archive.infos() {
+            if let Some((start, end)) = args.range {
+                if info.id < start || info.id > end {
+                    continue;
+                }
+            }
+            if args.map.is_some_and(|map| info.map != map) {
+                continue;
+            }
+            ret.push(info);
+        }
+    } else {
+        return Err("Please select graphics by --frames, --range or --map".into());
+    }
+
+    Ok(ret)
+}
+
+fn select_graphics<'a>(
+    args: &ArgParse,
+    archive: &'a Archive,
+) -> Result<Vec<&'a GraphicInfo>, Box<dyn std::error::Error + Send + Sync>> {
+    // 圖片版本 > 2 的功能尚未完成，先行跳過
+    let mut supported = vec![];
+    for info in select_infos(args, archive)? {
+        if archive.header(info.id)?.version > 1 {
+            warn!("Unsupported graphic version (id: {})", info.id);
+            continue;
+        }
+        supported.push(info);
+    }
+
+    Ok(supported)
+}
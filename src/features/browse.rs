@@ -0,0 +1,288 @@
+use std::io::{self, Write};
+use crossterm::{cursor, execute, queue};
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::style::{Attribute, Color, Print, SetAttribute, SetForegroundColor};
+use crossterm::terminal::{self, Clear, ClearType};
+use log::{debug, info};
+use crate::data_structure::graphic::{GraphicHeader, GraphicInfo, Palette};
+use crate::data_structure::terminal::ColorMode;
+use crate::features::ArgParse;
+use crate::features::sink::Sink;
+use crate::resource::archive::Archive;
+
+const LIST_WIDTH: u16 = 14;
+
+// 進入全螢幕模式，離開（包含發生錯誤）時還原終端機
+struct Screen;
+
+impl Screen {
+    fn enter() -> io::Result<Self> {
+        terminal::enable_raw_mode()?;
+        execute!(io::stdout(), terminal::EnterAlternateScreen, cursor::Hide)?;
+
+        Ok(Self)
+    }
+}
+
+impl Drop for Screen {
+    fn drop(&mut self) {
+        let _ = execute!(io::stdout(), cursor::Show, terminal::LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+struct Browser<'a> {
+    archive: &'a Archive,
+    palettes: Vec<(&'a str, &'a Palette)>,
+    palette: usize,
+    output: &'a str,
+    filter: String,
+    filtering: bool,
+    matches: Vec<&'a GraphicInfo>,
+    selected: usize,
+    scroll: usize,
+    message: String,
+    colors: ColorMode,
+}
+
+pub fn browse_graphics(
+    args: &clap::ArgMatches,
+    archive: &Archive,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    info!("Parsing sub command arguments");
+    let result = ArgParse::parse(args)?;
+    info!("Parsed sub command arguments");
+    debug!("{:?}", result);
+
+    let palettes = archive.palettes();
+    let palette = match result.palette_name {
+        Some(name) => palettes.iter().position(|(n, _)| *n == name).ok_or_else(|| format!("Palette not found: {}", name))?,
+        None => 0,
+    };
+    let mut browser = Browser {
+        archive,
+        palettes,
+        palette,
+        output: result.output.unwrap(),
+        filter: String::new(),
+        filtering: false,
+        matches: archive.infos().iter().collect(),
+        selected: 0,
+        scroll: 0,
+        message: String::new(),
+        colors: ColorMode::detect(),
+    };
+
+    let _screen = Screen::enter()?;
+    let mut stdout = io::stdout();
+    loop {
+        browser.draw(&mut stdout)?;
+        if let Event::Key(key) = event::read()? {
+            if key.kind == KeyEventKind::Press && !browser.on_key(key) {
+                break;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+impl<'a> Browser<'a> {
+    // 回傳 false 表示離開
+    fn on_key(&mut self, key: KeyEvent) -> bool {
+        if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+            return false;
+        }
+
+        if self.filtering {
+            match key.code {
+                KeyCode::Char(c) => self.filter.push(c),
+                KeyCode::Backspace => {
+                    self.filter.pop();
+                },
+                KeyCode::Esc => {
+                    self.filter.clear();
+                    self.filtering = false;
+                },
+                KeyCode::Enter => self.filtering = false,
+                _ => return true,
+            }
+            self.refilter();
+            return true;
+        }
+
+        let page = self.list_height().max(1);
+        self.message.clear();
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return false,
+            KeyCode::Up | KeyCode::Char('k') => self.select(self.selected as isize - 1),
+            KeyCode::Down | KeyCode::Char('j') => self.select(self.selected as isize + 1),
+            KeyCode::PageUp => self.select(self.selected as isize - page as isize),
+            KeyCode::PageDown => self.select(self.selected as isize + page as isize),
+            KeyCode::Home | KeyCode::Char('g') => self.select(0),
+            KeyCode::End | KeyCode::Char('G') => self.select(self.matches.len() as isize - 1),
+            KeyCode::Char('/') => self.filtering = true,
+            KeyCode::Char('p') => self.palette = (self.palette + 1) % self.palettes.len(),
+            KeyCode::Char('P') => self.palette = (self.palette + self.palettes.len() - 1) % self.palettes.len(),
+            KeyCode::Char('e') => {
+                self.message = match self.export() {
+                    Ok(message) => message,
+                    Err(e) => format!("Failed to export: {}", e),
+                };
+            },
+            _ => {},
+        }
+
+        true
+    }
+
+    // 以 map:N 篩選地圖編號，否則篩選 id 的開頭
+    fn refilter(&mut self) {
+        let filter = self.filter.trim();
+        let map = filter.strip_prefix("map:").or_else(|| filter.strip_prefix("m:")).map(|map| map.trim().parse::<u32>());
+
+        self.matches = self.archive.infos().iter().filter(|info| match &map {
+            Some(Ok(map)) => info.map == *map,
+            Some(Err(_)) => false,
+            None => info.id.to_string().starts_with(filter),
+        }).collect();
+        self.select(0);
+    }
+
+    fn select(&mut self, index: isize) {
+        self.selected = index.clamp(0, (self.matches.len() as isize - 1).max(0)) as usize;
+    }
+
+    fn list_height(&self) -> usize {
+        terminal::size().map_or(0, |(_, rows)| rows.saturating_sub(2) as usize)
+    }
+
+    fn export(&self) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+        let info = self.matches.get(self.selected).ok_or("No graphic selected")?;
+        let (name, palette) = self.palettes[self.palette];
+        let image = self.archive.render(info.id, palette)?.ok_or_else(|| format!("Empty Graphic Data (id: {})", info.id))?;
+
+        let mut png = vec![];
+        image.to_png(&mut png)?;
+        let file = format!("{}_{}.png", info.id, name);
+        let mut sink = Sink::directory(self.output);
+        sink.write(&file, &png)?;
+        sink.finish()?;
+
+        Ok(format!("Exported {}/{}", self.output.trim_end_matches('/'), file))
+    }
+
+    fn draw<W: Write>(&mut self, w: &mut W) -> io::Result<()> {
+        let (columns, rows) = terminal::size()?;
+        let height = rows.saturating_sub(2) as usize;
+        if self.selected < self.scroll {
+            self.scroll = self.selected;
+        } else if height > 0 && self.selected >= self.scroll + height {
+            self.scroll = self.selected + 1 - height;
+        }
+
+        queue!(w, Clear(ClearType::All), cursor::MoveTo(0, 0))?;
+        let filter = format!("/{}", self.filter);
+        if self.filtering {
+            queue!(w, SetAttribute(Attribute::Reverse), Print(fit(&filter, LIST_WIDTH as usize)), SetAttribute(Attribute::Reset))?;
+        } else {
+            queue!(w, Print(fit(&filter, LIST_WIDTH as usize)))?;
+        }
+
+        // 與 info 的表格相同：圖片過大以黃色標示，兩個檔案不一致以紅色標示
+        for (row, info) in self.matches.iter().enumerate().skip(self.scroll).take(height) {
+            queue!(w, cursor::MoveTo(0, (row - self.scroll + 1) as u16))?;
+            if let Some(color) = self.status_color(info) {
+                queue!(w, SetForegroundColor(color), SetAttribute(Attribute::Bold))?;
+            }
+            if row == self.selected {
+                queue!(w, SetAttribute(Attribute::Reverse))?;
+            }
+            queue!(w, Print(format!("{:>width$}", info.id, width = LIST_WIDTH as usize - 1)), SetAttribute(Attribute::Reset))?;
+        }
+
+        if let Some(info) = self.matches.get(self.selected) {
+            self.draw_detail(w, info, LIST_WIDTH + 1, columns.saturating_sub(LIST_WIDTH + 1), height as u16)?;
+        }
+
+        let status = format!(
+            "{}/{}  [/] filter  [p] palette: {}  [e] export  [q] quit  {}",
+            if self.matches.is_empty() { 0 } else { self.selected + 1 },
+            self.matches.len(),
+            self.palettes[self.palette].0,
+            self.message,
+        );
+        queue!(w, cursor::MoveTo(0, rows.saturating_sub(1)), Print(fit(&status, columns as usize)))?;
+
+        w.flush()
+    }
+
+    fn draw_detail<W: Write>(&self, w: &mut W, info: &GraphicInfo, x: u16, width: u16, height: u16) -> io::Result<()> {
+        let header = self.archive.header(info.id);
+        let value = |f: &dyn Fn(&GraphicHeader) -> String| header.as_ref().map_or_else(|_| "?".to_string(), f);
+
+        let mut lines = vec![
+            ("", "GraphicInfo.bin".to_string(), "Graphic.bin".to_string()),
+            ("id", info.id.to_string(), "-".to_string()),
+            ("address", info.address.to_string(), "-".to_string()),
+            ("length", info.length.to_string(), value(&|h| h.length.to_string())),
+            ("width", info.width.to_string(), value(&|h| h.width.to_string())),
+            ("height", info.height.to_string(), value(&|h| h.height.to_string())),
+            ("offset", format!("{}, {}", info.offset_x, info.offset_y), "-".to_string()),
+            ("tile", format!("{} x {}", info.tile_east, info.tile_south), "-".to_string()),
+            ("access", info.access.to_string(), "-".to_string()),
+            ("unknown", format!("{:?}", info.unknown), value(&|h| h.unknown.to_string())),
+            ("map", info.map.to_string(), "-".to_string()),
+            ("mark", "-".to_string(), value(&|h| h.mark.iter().collect())),
+            ("version", "-".to_string(), value(&|h| h.version.to_string())),
+        ];
+        if let Err(e) = &header {
+            lines.push(("error", e.to_string(), String::new()));
+        }
+
+        for (row, (name, info_value, header_value)) in lines.iter().enumerate().take(height as usize) {
+            let text = format!("{:<8} {:<20} {}", name, info_value, header_value);
+            queue!(w, cursor::MoveTo(x, row as u16 + 1))?;
+            let shared = ["length", "width", "height"].contains(name);
+            if shared && info_value != header_value {
+                queue!(w, SetForegroundColor(Color::Red), SetAttribute(Attribute::Bold))?;
+            } else if ["width", "height"].contains(name) && !info.valid_perimeter() {
+                queue!(w, SetForegroundColor(Color::Yellow), SetAttribute(Attribute::Bold))?;
+            }
+            queue!(w, Print(fit(&text, width as usize)), SetAttribute(Attribute::Reset))?;
+        }
+
+        // 預覽圖顯示於欄位下方的剩餘空間
+        let top = lines.len() as u16 + 2;
+        if top >= height || !matches!(&header, Ok(h) if h.version <= 1) || !info.valid_perimeter() {
+            return Ok(());
+        }
+        let image = match self.archive.render(info.id, self.palettes[self.palette].1) {
+            Ok(Some(image)) => image,
+            Ok(None) => return Ok(()),
+            Err(e) => {
+                queue!(w, cursor::MoveTo(x, top), Print(fit(&e.to_string(), width as usize)))?;
+                return Ok(());
+            },
+        };
+        for (row, line) in image.to_half_blocks(width as u32, (height - top + 1) as u32, self.colors).iter().enumerate() {
+            queue!(w, cursor::MoveTo(x, top + row as u16), Print(line))?;
+        }
+
+        Ok(())
+    }
+
+    fn status_color(&self, info: &GraphicInfo) -> Option<Color> {
+        if !info.valid_perimeter() {
+            return Some(Color::Yellow);
+        }
+        match self.archive.header(info.id) {
+            Ok(header) if info != &header => Some(Color::Red),
+            _ => None,
+        }
+    }
+}
+
+fn fit(text: &str, width: usize) -> String {
+    text.chars().take(width).collect()
+}
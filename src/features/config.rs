@@ -0,0 +1,303 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use log::{debug, info, warn};
+use serde::Deserialize;
+
+// 未指定 --config 時，讀取目前目錄下的設定檔（若存在）
+pub const CONFIG_NAME: &str = "xgate-tool.yaml";
+// 可由設定檔指定預設 --output 的子命令
+pub const OUTPUT_SUBCOMMANDS: [&str; 5] = ["dump", "atlas", "montage", "gallery", "browse"];
+const MAIN_SET: &str = "main";
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+// 一組對應的 <GraphicInfo.bin> 及 <Graphic.bin>，動畫檔案目前僅用於列出
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ArchiveSet {
+    pub graphic_info: PathBuf,
+    pub graphic: PathBuf,
+    pub palette: Option<PathBuf>,
+    pub anime_info: Option<PathBuf>,
+    pub anime: Option<PathBuf>,
+}
+
+// 設定檔中的相對路徑以設定檔所在的目錄為準，sets 的路徑則以 game_dir 為準（若有）
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    game_dir: Option<PathBuf>,
+    default_set: Option<String>,
+    sets: BTreeMap<String, ArchiveSet>,
+    palette: Option<PathBuf>,
+    index_cache: Option<PathBuf>,
+    output: BTreeMap<String, PathBuf>,
+    #[serde(skip)]
+    base: PathBuf,
+}
+
+// 實際用於開啟 Archive 的路徑
+#[derive(Debug)]
+pub struct Source {
+    pub graphic_info: String,
+    pub graphic: String,
+    pub palette: Option<String>,
+    pub index_cache: Option<String>,
+}
+
+impl Config {
+    // 明確指定的設定檔必須存在，預設的設定檔則可省略
+    pub fn find(path: Option<&str>) -> Result<Self> {
+        match path {
+            Some(path) => Self::load(Path::new(path)),
+            None if Path::new(CONFIG_NAME).is_file() => Self::load(Path::new(CONFIG_NAME)),
+            None => Ok(Self::default()),
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        info!("Loading the config from {}", path.display());
+        let text = fs::read_to_string(path).map_err(|e| format!("Failed to read the config {}: {}", path.display(), e))?;
+        let base = path.parent().unwrap_or_else(|| Path::new("")).to_path_buf();
+        let config = Self::parse(&text, base).map_err(|e| format!("Invalid config {}: {}", path.display(), e))?;
+        debug!("{:?}", config);
+
+        Ok(config)
+    }
+
+    fn parse(text: &str, base: PathBuf) -> Result<Self> {
+        let mut config: Self = serde_yaml::from_str(text)?;
+        if let Some(name) = config.output.keys().find(|name| !OUTPUT_SUBCOMMANDS.contains(&name.as_str())) {
+            return Err(format!("Unknown sub command in output: {}, expected one of {}", name, OUTPUT_SUBCOMMANDS.join(", ")).into());
+        }
+        config.base = base;
+
+        Ok(config)
+    }
+
+    // 子命令未指定 --output（或 dump 的 --archive）時，使用設定檔中的預設位置
+    pub fn default_output(&self, app: &clap::ArgMatches) -> Option<PathBuf> {
+        match app.subcommand() {
+            (name, Some(sub_args)) if sub_args.occurrences_of("output") == 0 && !sub_args.is_present("archive") => {
+                self.output.get(name).map(|path| self.base.join(path))
+            },
+            _ => None,
+        }
+    }
+
+    pub fn default_set(&self) -> Option<&str> {
+        self.default_set.as_deref()
+    }
+
+    // --game-dir 優先於設定檔的 game_dir，設定檔中同名的 set 覆蓋自動搜尋的結果
+    pub fn sets(&self, game_dir: Option<&str>) -> Result<BTreeMap<String, ArchiveSet>> {
+        let game_dir = match game_dir {
+            Some(dir) => Some(PathBuf::from(dir)),
+            None => self.game_dir.as_ref().map(|dir| self.base.join(dir)),
+        };
+
+        let mut ret = match &game_dir {
+            Some(dir) => discover(dir)?,
+            None => BTreeMap::new(),
+        };
+        let root = game_dir.unwrap_or_else(|| self.base.clone());
+        for (name, set) in &self.sets {
+            ret.insert(name.clone(), ArchiveSet {
+                graphic_info: root.join(&set.graphic_info),
+                graphic: root.join(&set.graphic),
+                palette: set.palette.as_ref().map(|path| root.join(path)),
+                anime_info: set.anime_info.as_ref().map(|path| root.join(path)),
+                anime: set.anime.as_ref().map(|path| root.join(path)),
+            });
+        }
+
+        Ok(ret)
+    }
+
+    // 命令列的參數優先，未指定檔案路徑時由 --set 選擇
+    pub fn resolve(&self, app: &clap::ArgMatches, sets: &BTreeMap<String, ArchiveSet>) -> Result<Source> {
+        let palette = self.palette.as_ref().map(|path| self.base.join(path));
+        let (graphic_info, graphic, palette) = match (app.value_of("GraphicInfo"), app.value_of("Graphic")) {
+            (Some(graphic_info), Some(graphic)) => (PathBuf::from(graphic_info), PathBuf::from(graphic), palette),
+            (Some(_), None) => return Err("Path of <Graphic.bin> is required".into()),
+            _ => {
+                let (name, set) = select_set(sets, app.value_of("set").or_else(|| self.default_set()))?;
+                info!("Using archive set: {}", name);
+                (set.graphic_info.clone(), set.graphic.clone(), set.palette.clone().or(palette))
+            },
+        };
+        let palette = match app.value_of("palette") {
+            Some(path) => Some(path.to_string()),
+            None => palette.as_deref().map(path_str).transpose()?,
+        };
+        let index_cache = match app.value_of("index_cache") {
+            Some(path) => Some(path.to_string()),
+            None => self.index_cache.as_ref().map(|path| path_str(&self.base.join(path))).transpose()?,
+        };
+
+        Ok(Source {graphic_info: path_str(&graphic_info)?, graphic: path_str(&graphic)?, palette, index_cache})
+    }
+}
+
+fn path_str(path: &Path) -> Result<String> {
+    Ok(path.to_str().ok_or_else(|| format!("Invalid path: {}", path.display()))?.to_string())
+}
+
+// 未指定名稱時，優先使用 main，只有一組時使用該組
+pub fn select_set<'a>(
+    sets: &'a BTreeMap<String, ArchiveSet>,
+    name: Option<&str>,
+) -> Result<(&'a str, &'a ArchiveSet)> {
+    let names = || sets.keys().map(|name| name.as_str()).collect::<Vec<_>>().join(", ");
+    let found = match name {
+        Some(name) => sets.get_key_value(name)
+            .ok_or_else(|| format!("Archive set not found: {}, available sets: {}", name, names()))?,
+        None if sets.is_empty() => {
+            return Err("Path of <GraphicInfo.bin> is required, or specify --game-dir or --config".into());
+        },
+        None => match sets.get_key_value(MAIN_SET) {
+            Some(found) => found,
+            None if sets.len() == 1 => sets.iter().next().unwrap(),
+            None => return Err(format!("Multiple archive sets found, please choose one by --set: {}", names()).into()),
+        },
+    };
+
+    Ok((found.0.as_str(), found.1))
+}
+
+// 搜尋 bin/GraphicInfo*.bin 及相同後綴的 bin/Graphic*.bin、bin/AnimeInfo*.bin、bin/Anime*.bin，
+// 後綴如 _66、Ex_5、_PUK2_2，同一系列只保留版本最新的一組；檔名不區分大小寫
+pub fn discover(game_dir: &Path) -> Result<BTreeMap<String, ArchiveSet>> {
+    info!("Discovering archive sets in {}", game_dir.display());
+    let bin = find_entry(game_dir, "bin")?
+        .filter(|path| path.is_dir())
+        .ok_or_else(|| format!("Directory not found: {}", game_dir.join("bin").display()))?;
+    let palette = find_entry(&bin, "pal")?.filter(|path| path.is_dir() && has_cgp(path));
+
+    let mut found: BTreeMap<String, (Option<u32>, ArchiveSet)> = BTreeMap::new();
+    for entry in fs::read_dir(&bin)? {
+        let path = entry?.path();
+        let file_name = path.file_name().unwrap_or_default().to_string_lossy().into_owned();
+        let lower = file_name.to_lowercase();
+        if !path.is_file() || !lower.starts_with("graphicinfo") || !lower.ends_with(".bin") {
+            continue;
+        }
+        let suffix = &file_name["graphicinfo".len()..file_name.len() - ".bin".len()];
+        let graphic = match find_entry(&bin, &format!("graphic{}.bin", suffix))? {
+            Some(graphic) => graphic,
+            None => {
+                warn!("Ignored {} without the matching Graphic{}.bin", path.display(), suffix);
+                continue;
+            },
+        };
+
+        let (series, version) = split_version(suffix);
+        let name = if series.is_empty() { MAIN_SET.to_string() } else { series.to_lowercase() };
+        debug!("Found archive set {} (version: {:?}): {}", name, version, path.display());
+        if found.get(&name).is_some_and(|(latest, _)| *latest >= version) {
+            continue;
+        }
+        found.insert(name, (version, ArchiveSet {
+            graphic_info: path.clone(),
+            graphic,
+            palette: palette.clone(),
+            anime_info: find_entry(&bin, &format!("animeinfo{}.bin", suffix))?,
+            anime: find_entry(&bin, &format!("anime{}.bin", suffix))?,
+        }));
+    }
+    info!("Discovered {} archive sets", found.len());
+
+    Ok(found.into_iter().map(|(name, (_, set))| (name, set)).collect())
+}
+
+// 後綴最後一段為數字時視為版本，例如 Ex_5 為 (Ex, 5)、_66 為 ("", 66)
+fn split_version(suffix: &str) -> (&str, Option<u32>) {
+    let suffix = suffix.trim_start_matches('_');
+    let (series, version) = match suffix.rsplit_once('_') {
+        Some((series, version)) => (series, version),
+        None => ("", suffix),
+    };
+
+    match version.parse::<u32>() {
+        Ok(version) => (series, Some(version)),
+        Err(_) => (suffix, None),
+    }
+}
+
+fn find_entry(dir: &Path, name: &str) -> io::Result<Option<PathBuf>> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.file_name().is_some_and(|n| n.to_string_lossy().eq_ignore_ascii_case(name)) {
+            return Ok(Some(path));
+        }
+    }
+
+    Ok(None)
+}
+
+fn has_cgp(dir: &Path) -> bool {
+    fs::read_dir(dir).is_ok_and(|entries| entries.filter_map(|entry| entry.ok()).any(|entry| {
+        entry.path().extension().is_some_and(|ext| ext.eq_ignore_ascii_case("cgp"))
+    }))
+}
+
+pub fn list_sets(
+    sets: &BTreeMap<String, ArchiveSet>,
+    default: Option<&str>,
+) -> Result<()> {
+    if sets.is_empty() {
+        return Err("No archive sets found, please specify --game-dir or --config".into());
+    }
+    let default = select_set(sets, default).ok().map(|(name, _)| name);
+
+    let optional = |path: &Option<PathBuf>| path.as_ref().map_or_else(|| "-".to_string(), |path| path.display().to_string());
+    for (name, set) in sets {
+        println!("{}{}", name, if Some(name.as_str()) == default { " (default)" } else { "" });
+        println!("    GraphicInfo: {}", set.graphic_info.display());
+        println!("    Graphic:     {}", set.graphic.display());
+        println!("    Palette:     {}", optional(&set.palette));
+        println!("    AnimeInfo:   {}", optional(&set.anime_info));
+        println!("    Anime:       {}", optional(&set.anime));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn split_version_suffix() {
+        assert_eq!(("", Some(66)), split_version("_66"));
+        assert_eq!(("Ex", Some(5)), split_version("Ex_5"));
+        assert_eq!(("PUK2", Some(2)), split_version("_PUK2_2"));
+        assert_eq!(("Joy", None), split_version("_Joy"));
+        assert_eq!(("", None), split_version(""));
+    }
+
+    #[test]
+    fn parse_config() {
+        let text = "
+default_set: ex
+sets:
+    ex:
+        graphic_info: bin/GraphicInfoEx_5.bin
+        graphic: bin/GraphicEx_5.bin
+palette: pal
+output:
+    dump: out/dump
+";
+        let config = Config::parse(text, PathBuf::from("conf")).unwrap();
+        let sets = config.sets(None).unwrap();
+
+        assert_eq!(Some("ex"), config.default_set());
+        assert_eq!(Some(&PathBuf::from("out/dump")), config.output.get("dump"));
+        assert_eq!(Path::new("conf/bin/GraphicEx_5.bin"), sets["ex"].graphic);
+        assert_eq!(None, sets["ex"].palette);
+        assert!(Config::parse("output: {info: out}", PathBuf::new()).is_err());
+        assert!(Config::parse("unknown: 1", PathBuf::new()).is_err());
+    }
+}
@@ -1,123 +1,673 @@
-use crate::data_structure::graphic::{GraphicInfo, GraphicHeader, Graphic};
+use crate::data_structure::graphic::{Graphic, GraphicHeader, GraphicInfo, Palette};
+use crate::data_structure::image::{Canvas, Rect, RgbaImage};
+use crate::data_structure::scale::{Scale, ScaleError};
 use crate::features::ArgParse;
-use crate::resource::graphic::{GraphicInfoResource, GraphicResource, PaletteResource};
-use log::{debug, info, trace};
-use std::io::SeekFrom;
+use crate::features::manifest::{self, Fingerprint, Manifest, ManifestEntry};
+use crate::features::sink::Sink;
+use crate::features::template::{Field, NameFields, NameTemplate};
+use crate::resource::archive::Archive;
+use log::{debug, info, trace, warn};
+use serde::Serialize;
+use std::fs;
+use std::io;
+use std::sync::Arc;
+
+// 每匯出這麼多張圖片保存一次 manifest
+const SAVE_INTERVAL: u32 = 500;
+// 畫布的邊長上限，避免配置過大的記憶體
+const MAX_CANVAS: u32 = 16384;
+
+#[derive(Debug, Serialize)]
+struct RawSidecar<'a> {
+    id: u32,
+    width: u32,
+    height: u32,
+    offset_x: i32,
+    offset_y: i32,
+    palette: &'a str,
+    version: u8,
+    compressed: bool,
+    length: usize,
+}
+
+// 圖片的完整資訊及匯出參數，供遊戲引擎的匯入腳本使用
+#[derive(Debug, Serialize)]
+struct Metadata<'a> {
+    file: String,
+    format: &'a str,
+    indexed: bool,
+    palette: &'a str,
+    info: GraphicInfo,
+    header: GraphicHeader,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    trimmed: Option<Trimmed>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    canvas: Option<CanvasSize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    scaled: Option<Scaled>,
+}
+
+// 放大後的大小及偏移量，已去除透明邊緣時以去除後的圖片計算
+#[derive(Debug, Serialize)]
+struct Scaled {
+    factor: u32,
+    filter: String,
+    width: u32,
+    height: u32,
+    offset_x: i32,
+    offset_y: i32,
+}
+
+// 畫布大小及原點位置（已放大），錨點位於原點上
+#[derive(Debug, Serialize)]
+struct CanvasSize {
+    width: u32,
+    height: u32,
+    origin_x: i32,
+    origin_y: i32,
+}
+
+// 去除透明邊緣後，在原圖中的範圍及調整後的偏移量
+#[derive(Debug, Serialize)]
+struct Trimmed {
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+    offset_x: i32,
+    offset_y: i32,
+}
+
+// 輸出目的地及檔名樣板
+struct Layout<'a> {
+    sink: Sink,
+    source: &'a str,
+    template: NameTemplate,
+}
+
+impl<'a> Layout<'a> {
+    fn new(args: &'a ArgParse) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        let sink = match (args.output, args.archive) {
+            (_, Some(archive)) => Sink::archive(archive)?,
+            (Some(output), None) => Sink::directory(output),
+            (None, None) => return Err("No output chosen, please specify --output or --archive".into()),
+        };
+        let source = match args.name_template {
+            Some(template) => template,
+            None if args.all_palettes => "{id}_{palette}",
+            None => "{id}",
+        };
+        let template = NameTemplate::parse(source)?;
+        // 匯出所有調色盤時，需以調色盤名稱區分檔名
+        if args.all_palettes && !template.contains(Field::Palette) {
+            return Err("The name template must contain {palette} with --all-palettes".into());
+        }
+
+        Ok(Self {sink, source, template})
+    }
+
+    // 相對於輸出目錄的路徑
+    fn name(&self, fields: &NameFields, ext: &str) -> String {
+        format!("{}.{}", self.template.render(fields), ext)
+    }
+
+    fn write(&mut self, fields: &NameFields, ext: &str, bytes: &[u8]) -> Result<String, io::Error> {
+        let name = self.name(fields, ext);
+        self.sink.write(&name, bytes)?;
+
+        Ok(name)
+    }
+
+    fn remove(&self, name: &str) -> Result<(), io::Error> {
+        let output = match self.sink.directory_path() {
+            Some(output) => output,
+            None => return Ok(()),
+        };
+
+        trace!("Removing {}", name);
+        match fs::remove_file(output.join(name)) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
+    }
+}
 
 pub fn dump_graphics(
     args: &clap::ArgMatches,
-    resources: &mut (
-        GraphicInfoResource,
-        GraphicResource,
-        Option<PaletteResource>,
-    ),
-) -> Result<(), Box<dyn std::error::Error>> {
+    archive: &Archive,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     info!("Parsing sub command arguments");
     let result = ArgParse::parse(args)?;
     info!("Parsed sub command arguments");
     debug!("{:?}", result);
 
-    if result.id.is_some() {
-        let (graphic_info, mut graphic) =
-            find_by_id(result.id.unwrap(), &mut resources.0, &mut resources.1)?;
-        let palette = resources.2.as_mut().unwrap().build()?;
-        debug!("{:?}", graphic);
-        debug!("{:?}", palette);
-
-        info!("Decoding graphic data");
-        if graphic.header.version & 1 == 1 {
-            graphic.data = graphic.data.decode();
+    let mut layout = Layout::new(&result)?;
+    scale_of(&result)?;
+    if let Some((width, height)) = result.canvas {
+        if width == 0 || height == 0 {
+            return Err("The canvas size should be greater than 0".into());
         }
-        info!("Decoded graphic data");
-
-        info!("Building image");
-        if let Some(image) = graphic.build_v1_image(&graphic_info, &palette)? {
-            image.save(
-                format!(
-                    "{}/{}.bmp",
-                    result.output.unwrap(),
-                    graphic_info.id
-                )
-            )?;
+        if width > MAX_CANVAS || height > MAX_CANVAS {
+            return Err(format!("The canvas size should not be greater than {}", MAX_CANVAS).into());
         }
-        info!("Built image");
-    } else if result.all {
-        let palette = resources.2.as_mut().unwrap().build()?;
-
-        for (info, mut graphic) in find_all(&mut resources.0, &mut resources.1)? {
-            trace!("Graphic ID: {}", info.id);
-            if graphic.header.version & 1 == 1 {
-                graphic.data = graphic.data.decode();
+    }
+    let palettes = select_palettes(&result, archive)?;
+    for (name, _) in &palettes {
+        info!("Using palette: {}", name);
+    }
+
+    // 封存檔每次皆重新寫入，僅輸出至目錄時使用 manifest
+    let mut manifest = match layout.sink.directory_path() {
+        Some(output) => Some(Manifest::load(output)?),
+        None => None,
+    };
+    let palette_hash = manifest::palette_fingerprint(&palettes);
+    let options_hash = options_fingerprint(&result, &layout);
+
+    let (mut dumped, mut skipped) = (0, 0);
+    let mut sidecars = vec![];
+    let mut metadata = vec![];
+    let ids = select_ids(&result, archive)?;
+    // 發生錯誤時仍保存已完成的部分
+    let dump_all = || -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        for id in ids {
+            trace!("Graphic ID: {}", id);
+            let info = archive.info(id).ok_or_else(|| format!("Graphic not found (id: {})", id))?;
+            // 有索引快取時不需讀取圖片即可判斷是否變更
+            let mut graphic = None;
+            let payload_hash = match archive.payload_hash(id) {
+                Some(hash) => hash.to_string(),
+                None => {
+                    let raw = archive.raw(id)?;
+                    let hash = manifest::payload_fingerprint(info, &raw)?;
+                    graphic = Some(raw);
+                    hash
+                },
+            };
+
+            let fresh = match (&manifest, layout.sink.directory_path()) {
+                (Some(manifest), Some(output)) => manifest.is_fresh(id, &payload_hash, &palette_hash, &options_hash, output),
+                _ => false,
+            };
+            if !result.force && fresh {
+                trace!("Skipping unchanged graphic (id: {})", id);
+                skipped += 1;
+                // index.csv 仍需包含略過的圖片
+                if result.sidecar == Some("csv") {
+                    let graphic = match graphic {
+                        Some(graphic) => graphic,
+                        None => archive.raw(id)?,
+                    };
+                    let bytes = raw_data(archive, info, &graphic, &result)?;
+                    if !bytes.is_empty() {
+                        sidecars.push(raw_sidecar(info, &graphic, palettes[0].0, &result, bytes.len()));
+                    }
+                }
+                // index.json 亦同，僅包含上次實際輸出的圖片
+                if result.metadata == Some("index") {
+                    let files = &manifest.as_ref().and_then(|m| m.get(id)).unwrap().files;
+                    let bounds = if result.trim { trim_bounds(archive, id)? } else { None };
+                    let header = archive.header(id)?;
+                    for &(name, _) in &palettes {
+                        let file = layout.name(&name_fields(info, &header, name), result.format.unwrap());
+                        if files.contains(&file) {
+                            metadata.push(image_metadata(info, &header, bounds, name, file, &result)?);
+                        }
+                    }
+                }
+                continue;
+            }
+
+            let graphic = match graphic {
+                Some(graphic) => graphic,
+                None => archive.raw(id)?,
+            };
+            let files = if result.raw {
+                let (files, sidecar) = save_raw(info, &graphic, archive, palettes[0].0, &result, &mut layout)?;
+                sidecars.extend(sidecar);
+                files
+            } else {
+                save_images(info, &graphic.header, archive, &palettes, &result, &mut layout, &mut metadata)?
+            };
+            dumped += 1;
+
+            let manifest = match manifest.as_mut() {
+                Some(manifest) => manifest,
+                None => continue,
+            };
+            let entry = ManifestEntry {
+                payload: payload_hash,
+                palette: palette_hash.clone(),
+                options: options_hash.clone(),
+                files,
+            };
+            if let Some(old) = manifest.insert(id, entry.clone()) {
+                // 檔名樣板變更時，舊的檔案不會被覆寫
+                if result.prune {
+                    for file in old.files.iter().filter(|file| !entry.files.contains(file)) {
+                        layout.remove(file)?;
+                    }
+                }
+            }
+            // 定期保存，中斷後重新執行時可略過已完成的圖片
+            if dumped % SAVE_INTERVAL == 0 {
+                if let Some(output) = layout.sink.directory_path() {
+                    manifest.save(output)?;
+                }
             }
-            if let Some(image) = graphic.build_v1_image(&info, &palette)? {
-                image.save(
-                    format!(
-                        "{}/{}.bmp",
-                        result.output.unwrap(),
-                        info.id
-                    )
-                )?;
+        }
+
+        Ok(())
+    };
+
+    if let Err(e) = dump_all() {
+        if let (Some(manifest), Some(output)) = (manifest.as_ref(), layout.sink.directory_path()) {
+            if let Err(save_error) = manifest.save(output) {
+                warn!("Failed to save the manifest: {}", save_error);
             }
         }
+        return Err(e);
     }
 
+    if let (Some(manifest), Some(output)) = (manifest.as_mut(), layout.sink.directory_path()) {
+        if result.prune {
+            prune(manifest, archive, &layout)?;
+        }
+        manifest.save(output)?;
+    }
+    info!("Dumped {} graphics, skipped {} unchanged graphics", dumped, skipped);
+
+    if result.sidecar == Some("csv") {
+        save_csv(&sidecars, &mut layout.sink)?;
+    }
+    if result.metadata == Some("index") {
+        layout.sink.write("index.json", &serde_json::to_vec_pretty(&metadata)?)?;
+    }
+    layout.sink.finish()?;
+
     Ok(())
 }
 
-fn get_graphic(info: &GraphicInfo, header: &GraphicHeader, graphic: &mut GraphicResource) -> Result<Graphic, Box<dyn std::error::Error>> {
-    let graphic = match header.version {
-        0 | 1 => {
-            graphic.seek(SeekFrom::Start(info.address as u64))?;
-            Graphic::new_v1(
-                graphic.read(info.length as usize)?,
-            )?
-        },
-        2 | 3 => {
-            todo!()
+// 匯出選項，任一選項變更時需重新匯出所有圖片
+fn options_fingerprint(args: &ArgParse, layout: &Layout) -> String {
+    let options = format!(
+        "format={:?};indexed={};trim={};canvas={:?};origin={:?};debug_anchor={};scale={:?};scale_filter={:?};raw={};compressed={};sidecar={:?};metadata={:?};all_palettes={};template={}",
+        args.format, args.indexed, args.trim, args.canvas, args.origin, args.debug_anchor, args.scale, args.scale_filter,
+        args.raw, args.compressed, args.sidecar, args.metadata, args.all_palettes, layout.source,
+    );
+
+    Fingerprint::new().write(options.as_bytes()).hex()
+}
+
+// 刪除已不存在於 <GraphicInfo.bin> 的圖片
+fn prune(
+    manifest: &mut Manifest,
+    archive: &Archive,
+    layout: &Layout,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    for id in manifest.ids() {
+        if archive.info(id).is_some() {
+            continue;
+        }
+
+        info!("Pruning removed graphic (id: {})", id);
+        if let Some(entry) = manifest.remove(id) {
+            for file in &entry.files {
+                layout.remove(file)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn select_ids(
+    args: &ArgParse,
+    archive: &Archive,
+) -> Result<Vec<u32>, Box<dyn std::error::Error + Send + Sync>> {
+    let mut ret = vec![];
+
+    if let Some(id) = args.id {
+        if archive.info(id).is_none() {
+            return Err(format!("Graphic not found (id: {})", id).into());
         }
-        _ => {
-            panic!("Unknown version of graphic.");
+        ret.push(id);
+    } else if args.all {
+        for (info, header) in archive.iter_headers() {
+            // 圖片版本 > 2 的功能尚未完成，先行跳過
+            if header?.version > 1 {
+                continue;
+            }
+            ret.push(info.id);
         }
+    }
+
+    Ok(ret)
+}
+
+fn select_palettes<'a>(
+    args: &ArgParse,
+    archive: &'a Archive,
+) -> Result<Vec<(&'a str, &'a Palette)>, Box<dyn std::error::Error + Send + Sync>> {
+    if args.all_palettes {
+        return Ok(archive.palettes());
+    }
+
+    match args.palette_name {
+        Some(name) => match archive.palettes().into_iter().find(|(n, _)| *n == name) {
+            Some(palette) => Ok(vec![palette]),
+            None => Err(format!("Palette not found: {}", name).into()),
+        },
+        None => Ok(vec![(archive.palette_name(), archive.palette())]),
+    }
+}
+
+fn name_fields<'a>(info: &GraphicInfo, header: &GraphicHeader, palette: &'a str) -> NameFields<'a> {
+    NameFields {
+        id: info.id,
+        map: info.map,
+        version: header.version,
+        width: info.width,
+        height: info.height,
+        palette,
+    }
+}
+
+fn image_metadata<'a>(
+    info: &GraphicInfo,
+    header: &GraphicHeader,
+    bounds: Option<Rect>,
+    palette: &'a str,
+    file: String,
+    args: &'a ArgParse,
+) -> Result<Metadata<'a>, Box<dyn std::error::Error + Send + Sync>> {
+    let scale = scale_of(args).unwrap_or_default();
+    let too_large = || format!("The scaled graphic is too large (id: {})", info.id);
+    let canvas = match args.canvas {
+        Some(_) => {
+            let canvas = new_canvas(args);
+            let factor = scale.factor();
+            Some(CanvasSize {
+                width: canvas.image.width.checked_mul(factor).ok_or_else(too_large)?,
+                height: canvas.image.height.checked_mul(factor).ok_or_else(too_large)?,
+                origin_x: canvas.origin_x.checked_mul(factor as i32).ok_or_else(too_large)?,
+                origin_y: canvas.origin_y.checked_mul(factor as i32).ok_or_else(too_large)?,
+            })
+        },
+        None => None,
+    };
+    let scaled = match Some(scale).filter(|scale| scale.factor() > 1) {
+        Some(scale) => {
+            let scaled = bounds.map_or_else(|| info.clone(), |rect| info.cropped(rect))
+                .scaled(scale.factor())
+                .ok_or_else(too_large)?;
+            Some(Scaled {
+                factor: scale.factor(),
+                filter: scale.filter().to_string(),
+                width: scaled.width,
+                height: scaled.height,
+                offset_x: scaled.offset_x,
+                offset_y: scaled.offset_y,
+            })
+        },
+        None => None,
+    };
+
+    Ok(Metadata {
+        file,
+        format: args.format.unwrap(),
+        indexed: args.indexed,
+        palette,
+        info: info.clone(),
+        header: header.clone(),
+        trimmed: bounds.map(|rect| {
+            let cropped = info.cropped(rect);
+            Trimmed {
+                x: rect.x,
+                y: rect.y,
+                width: rect.width,
+                height: rect.height,
+                offset_x: cropped.offset_x,
+                offset_y: cropped.offset_y,
+            }
+        }),
+        canvas,
+        scaled,
+    })
+}
+
+fn scale_of(args: &ArgParse) -> Result<Scale, ScaleError> {
+    Scale::new(args.scale.unwrap_or(1), args.scale_filter.unwrap_or("nearest").parse()?)
+}
+
+fn new_canvas(args: &ArgParse) -> Canvas {
+    let (width, height) = args.canvas.unwrap();
+    match args.origin {
+        Some((x, y)) => Canvas::new(width, height, x, y),
+        None => Canvas::centered(width, height),
+    }
+}
+
+// 依偏移量將圖片繪製於固定大小的畫布上
+fn draw_on_canvas(info: &GraphicInfo, image: &RgbaImage, args: &ArgParse) -> RgbaImage {
+    let mut canvas = new_canvas(args);
+    canvas.draw(info, image);
+    if args.debug_anchor {
+        canvas.draw_bounds(info, [0x00, 0xff, 0x00, 0xff]);
+        canvas.draw_crosshair(4, [0xff, 0x00, 0x00, 0xff]);
+    }
+
+    canvas.image
+}
+
+// 不透明像素的範圍，與調色盤無關，空白或全透明的圖片回傳 None
+fn trim_bounds(archive: &Archive, id: u32) -> Result<Option<Rect>, Box<dyn std::error::Error + Send + Sync>> {
+    Ok(archive.indexed(id)?.and_then(|image| image.opaque_bounds()))
+}
+
+fn save_images<'a>(
+    info: &GraphicInfo,
+    header: &GraphicHeader,
+    archive: &Archive,
+    palettes: &[(&'a str, &Palette)],
+    args: &'a ArgParse,
+    layout: &mut Layout,
+    metadata: &mut Vec<Metadata<'a>>,
+) -> Result<Vec<String>, Box<dyn std::error::Error + Send + Sync>> {
+    let id = info.id;
+    let format = args.format.unwrap();
+    let mut files = vec![];
+
+    let bounds = if args.trim {
+        match trim_bounds(archive, id)? {
+            Some(bounds) => Some(bounds),
+            None => {
+                warn!("Empty Graphic Data after trimming (id: {})", id);
+                return Ok(files);
+            },
+        }
+    } else {
+        None
     };
 
-    Ok(graphic)
+    for (name, palette) in palettes {
+        let bytes = match encode_image(id, archive, palette, bounds, args)? {
+            Some(bytes) => bytes,
+            None => {
+                warn!("Empty Graphic Data (id: {})", id);
+                return Ok(files);
+            },
+        };
+
+        let fields = name_fields(info, header, name);
+        let file = layout.write(&fields, format, &bytes)?;
+        files.push(file.clone());
+
+        match args.metadata {
+            Some("json") => {
+                let json = serde_json::to_vec_pretty(&image_metadata(info, header, bounds, name, file, args)?)?;
+                files.push(layout.write(&fields, "json", &json)?);
+            },
+            Some("index") => metadata.push(image_metadata(info, header, bounds, name, file, args)?),
+            _ => {},
+        }
+    }
+
+    Ok(files)
 }
 
-fn find_by_id(
+fn encode_image(
     id: u32,
-    graphic_info_resource: &mut GraphicInfoResource,
-    graphic_resource: &mut GraphicResource,
-) -> Result<(GraphicInfo, Graphic), Box<dyn std::error::Error>> {
-    info!("Finding graphic by id = {}", id);
-    let graphic_info = graphic_info_resource.find(|gi| gi.id == id).unwrap();
-    debug!("Found graphic_info = {:?}", graphic_info);
-    info!("Finding graphic_header at {}", graphic_info.address);
-    graphic_resource.seek(SeekFrom::Start(graphic_info.address as u64))?;
-    let graphic_header = graphic_resource.read_header()?;
-    debug!("Found graphic_header = {:?}", graphic_header);
-
-    let graphic = get_graphic(&graphic_info, &graphic_header, graphic_resource)?;
-
-    Ok((graphic_info, graphic))
-}
-
-fn find_all(
-    graphic_info_resource: &mut GraphicInfoResource,
-    graphic_resource: &mut GraphicResource,
-) -> Result<Vec<(GraphicInfo, Graphic)>, Box<dyn std::error::Error>> {
+    archive: &Archive,
+    palette: &Palette,
+    bounds: Option<Rect>,
+    args: &ArgParse,
+) -> Result<Option<Vec<u8>>, Box<dyn std::error::Error + Send + Sync>> {
+    let format = args.format.unwrap();
+    let scale = scale_of(args)?;
     let mut ret = vec![];
 
-    info!("Collecting all of GraphicInfo and GraphicHeader");
-    for graphic_info in graphic_info_resource {
-        graphic_resource.seek(SeekFrom::Start(graphic_info.address as u64))?;
-        let header = graphic_resource.read_header()?;
-        // 圖片版本 > 2 的功能尚未完成，先行跳過
-        if header.version <= 1 {
-            let graphic = get_graphic(&graphic_info, &header, graphic_resource)?;
-            ret.push((graphic_info, graphic));
+    if args.indexed {
+        let image = match (archive.indexed(id)?, bounds) {
+            (Some(image), Some(bounds)) => image.crop(bounds),
+            (Some(image), None) => image,
+            (None, _) => return Ok(None),
+        };
+        let image = if scale.factor() > 1 { image.scaled(scale)? } else { image };
+        match format {
+            "bmp" => image.to_bmp(palette, &mut ret)?,
+            #[cfg(feature = "png")]
+            "png" => image.to_png(palette, &mut ret)?,
+            _ => return Err(format!("Unsupported output format: {}", format).into()),
+        }
+    } else {
+        let image = match (archive.render(id, palette)?, bounds) {
+            (Some(image), Some(bounds)) => Arc::new(image.crop(bounds)),
+            (Some(image), None) => image,
+            (None, _) => return Ok(None),
+        };
+        let image = match args.canvas {
+            Some(_) => Arc::new(draw_on_canvas(archive.info(id).unwrap(), &image, args)),
+            None => image,
+        };
+        let image = if scale.factor() > 1 { Arc::new(image.scaled(scale)?) } else { image };
+        match format {
+            "bmp" => image.to_bmp().to_writer(&mut ret)?,
+            #[cfg(feature = "png")]
+            "png" => image.to_png(&mut ret)?,
+            _ => return Err(format!("Unsupported output format: {}", format).into()),
         }
     }
-    info!("Collected all of GraphicInfo and GraphicHeader");
 
-    Ok(ret)
+    Ok(Some(ret))
+}
+
+fn raw_data(
+    archive: &Archive,
+    info: &GraphicInfo,
+    graphic: &Graphic,
+    args: &ArgParse,
+) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>> {
+    if args.compressed {
+        Ok(graphic.data.bytes().to_vec())
+    } else {
+        Ok(archive.decoded(info.id)?.bytes().to_vec())
+    }
+}
+
+// 版本的最低位表示圖片資料經過 RLE 壓縮
+fn is_rle(graphic: &Graphic, args: &ArgParse) -> bool {
+    args.compressed && graphic.header.version & 1 == 1
+}
+
+fn raw_sidecar<'a>(
+    info: &GraphicInfo,
+    graphic: &Graphic,
+    palette: &'a str,
+    args: &ArgParse,
+    length: usize,
+) -> RawSidecar<'a> {
+    RawSidecar {
+        id: info.id,
+        width: info.width,
+        height: info.height,
+        offset_x: info.offset_x,
+        offset_y: info.offset_y,
+        palette,
+        version: graphic.header.version,
+        compressed: is_rle(graphic, args),
+        length,
+    }
+}
+
+fn save_raw<'a>(
+    info: &GraphicInfo,
+    graphic: &Graphic,
+    archive: &Archive,
+    palette: &'a str,
+    args: &ArgParse,
+    layout: &mut Layout,
+) -> Result<(Vec<String>, Option<RawSidecar<'a>>), Box<dyn std::error::Error + Send + Sync>> {
+    let bytes = raw_data(archive, info, graphic, args)?;
+    if bytes.is_empty() {
+        warn!("Empty Graphic Data (id: {})", info.id);
+        return Ok((vec![], None));
+    }
+    let ext = if is_rle(graphic, args) { "rle" } else { "raw" };
+    let fields = name_fields(info, &graphic.header, palette);
+    let mut files = vec![layout.write(&fields, ext, &bytes)?];
+
+    let sidecar = raw_sidecar(info, graphic, palette, args, bytes.len());
+    if args.sidecar == Some("json") {
+        files.push(layout.write(&fields, "json", &serde_json::to_vec_pretty(&sidecar)?)?);
+    }
+
+    Ok((files, Some(sidecar)))
+}
+
+fn save_csv(sidecars: &[RawSidecar], sink: &mut Sink) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let mut csv = String::from("id,width,height,offset_x,offset_y,palette,version,compressed,length\n");
+    for s in sidecars {
+        csv.push_str(&format!(
+            "{},{},{},{},{},{},{},{},{}\n",
+            s.id, s.width, s.height, s.offset_x, s.offset_y, s.palette, s.version, s.compressed, s.length,
+        ));
+    }
+    sink.write("index.csv", csv.as_bytes())?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::resource::graphic::{GraphicInfoResource, GraphicResource};
+
+    #[test]
+    fn reject_unknown_id() {
+        let dir = std::env::temp_dir().join(format!("xgate-tool-dump-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = |name: &str| dir.join(name).to_string_lossy().into_owned();
+
+        let info = GraphicInfo {
+            id: 7, address: 0, length: 20, offset_x: 0, offset_y: 0, width: 2, height: 2,
+            tile_east: 0, tile_south: 0, access: 0, unknown: [0; 5], map: 0,
+        };
+        fs::write(path("GraphicInfo.bin"), bincode::serialize(&info).unwrap()).unwrap();
+        fs::write(path("Graphic.bin"), []).unwrap();
+        let archive = Archive::new(
+            GraphicInfoResource::load(&path("GraphicInfo.bin")).unwrap(),
+            GraphicResource::load(&path("Graphic.bin")).unwrap(),
+        ).unwrap();
+
+        let app = clap::App::new("dump").arg(clap::Arg::with_name("graphic_id").long("id").takes_value(true));
+        let select = |id: &str| {
+            let matches = app.clone().get_matches_from(vec!["dump", "--id", id]);
+            select_ids(&ArgParse::parse(&matches).unwrap(), &archive).map_err(|e| e.to_string())
+        };
+        assert_eq!(Ok(vec![7]), select("7"));
+        assert_eq!(Err("Graphic not found (id: 5)".to_string()), select("5"));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
 }
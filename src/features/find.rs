@@ -0,0 +1,157 @@
+use std::cmp::Ordering;
+use std::fs::{self, File};
+use std::io::BufReader;
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+use log::{debug, info, trace, warn};
+use serde::{Serialize, Deserialize};
+use crate::data_structure::graphic::Palette;
+use crate::data_structure::image::RgbaImage;
+use crate::data_structure::phash::ImageHash;
+use crate::features::ArgParse;
+use crate::features::manifest::{Fingerprint, palette_fingerprint};
+use crate::resource::archive::Archive;
+
+// 索引格式變更時需遞增，舊的索引會自動重建
+const INDEX_VERSION: u32 = 1;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct HashIndex {
+    version: u32,
+    // 所有 GraphicInfo、<Graphic.bin> 及調色盤的雜湊，任一變更時重建
+    source: String,
+    entries: Vec<(u32, ImageHash)>,
+}
+
+pub fn find_image(
+    args: &clap::ArgMatches,
+    archive: &Archive,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    info!("Parsing sub command arguments");
+    let result = ArgParse::parse(args)?;
+    info!("Parsed sub command arguments");
+    debug!("{:?}", result);
+
+    let (name, palette) = match result.palette_name {
+        Some(name) => (name, archive.find_palette(name).ok_or_else(|| format!("Palette not found: {}", name))?),
+        None => (archive.palette_name(), archive.palette()),
+    };
+    let source = source_fingerprint(archive, name, palette)?;
+    let path = Path::new(result.index.unwrap());
+
+    let index = match load_index(path) {
+        Some(index) if !result.rebuild && index.version == INDEX_VERSION && index.source == source => index,
+        _ => {
+            info!("Building the image hash index");
+            let index = build_index(archive, palette, source);
+            if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(path, bincode::serialize(&index)?)?;
+            info!("Saved {} image hashes into {}", index.entries.len(), path.display());
+            index
+        },
+    };
+
+    let mut query = read_image(result.input.unwrap())?;
+    strip_background(&mut query);
+    let hash = ImageHash::new(&query).ok_or("The image is fully transparent")?;
+    debug!("{:?}", hash);
+
+    let mut ranked: Vec<(u32, u32, f64)> = index.entries.iter()
+        .map(|(id, candidate)| (*id, hash.distance(candidate), hash.aspect_difference(candidate)))
+        .collect();
+    ranked.sort_by(|a, b| a.1.cmp(&b.1).then(a.2.partial_cmp(&b.2).unwrap_or(Ordering::Equal)).then(a.0.cmp(&b.0)));
+
+    println!("id\tdistance\tmap\tsize");
+    for (id, distance, _) in ranked.into_iter().take(result.limit.unwrap() as usize) {
+        let info = archive.info(id).ok_or_else(|| format!("Graphic not found (id: {})", id))?;
+        println!("{}\t{}\t{}\t{}x{}", id, distance, info.map, info.width, info.height);
+    }
+
+    Ok(())
+}
+
+// 圖片的內容變更時 <Graphic.bin> 的大小或修改時間亦會改變；有索引快取時再加上每張圖片的內容雜湊
+fn source_fingerprint(
+    archive: &Archive,
+    name: &str,
+    palette: &Palette,
+) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+    let mut ret = Fingerprint::new();
+    for info in archive.infos() {
+        ret.write(&bincode::serialize(info)?);
+        if let Some(hash) = archive.payload_hash(info.id) {
+            ret.write(hash.as_bytes());
+        }
+    }
+    ret.write(palette_fingerprint(&[(name, palette)]).as_bytes());
+
+    let metadata = archive.graphic_metadata()?;
+    let modified = metadata.modified()?.duration_since(UNIX_EPOCH).unwrap_or_default();
+    ret.write(&metadata.len().to_le_bytes())
+        .write(&modified.as_secs().to_le_bytes())
+        .write(&modified.subsec_nanos().to_le_bytes());
+
+    Ok(ret.hex())
+}
+
+// 索引不存在或損毀時回傳 None
+fn load_index(path: &Path) -> Option<HashIndex> {
+    let bytes = fs::read(path).ok()?;
+    match bincode::deserialize(&bytes) {
+        Ok(index) => Some(index),
+        Err(e) => {
+            warn!("Ignored the broken image hash index {}: {}", path.display(), e);
+            None
+        },
+    }
+}
+
+fn build_index(archive: &Archive, palette: &Palette, source: String) -> HashIndex {
+    let mut entries = vec![];
+    for (info, header) in archive.iter_headers() {
+        trace!("Graphic ID: {}", info.id);
+        // 圖片版本 > 2 的功能尚未完成，先行跳過
+        if !info.valid_perimeter() || header.map_or(true, |h| h.version > 1) {
+            continue;
+        }
+        match archive.render(info.id, palette) {
+            Ok(Some(image)) => {
+                if let Some(hash) = ImageHash::new(&image) {
+                    entries.push((info.id, hash));
+                }
+            },
+            Ok(None) => {},
+            Err(e) => warn!("Failed to render the graphic (id: {}): {}", info.id, e),
+        }
+    }
+
+    HashIndex {version: INDEX_VERSION, source, entries}
+}
+
+fn read_image(path: &str) -> Result<RgbaImage, Box<dyn std::error::Error + Send + Sync>> {
+    match Path::new(path).extension().and_then(|ext| ext.to_str()).map(|ext| ext.to_lowercase()).as_deref() {
+        Some("png") => Ok(RgbaImage::from_png(BufReader::new(File::open(path)?))?),
+        Some("bmp") => Ok(RgbaImage::from_bmp(&bmp::open(path)?)),
+        _ => Err(format!("Unsupported image format: {}, only PNG and BMP are supported", path).into()),
+    }
+}
+
+// BMP 及截圖沒有透明度，四個角落顏色相同時視為背景
+fn strip_background(image: &mut RgbaImage) {
+    if image.width == 0 || image.height == 0 || image.pixels.chunks_exact(4).any(|p| p[3] != 0xff) {
+        return;
+    }
+    let (right, bottom) = (image.width - 1, image.height - 1);
+    let background = image.get_pixel(0, 0);
+    if [(right, 0), (0, bottom), (right, bottom)].iter().any(|&(x, y)| image.get_pixel(x, y) != background) {
+        return;
+    }
+
+    for pixel in image.pixels.chunks_exact_mut(4) {
+        if pixel == background {
+            pixel[3] = 0;
+        }
+    }
+}
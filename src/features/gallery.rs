@@ -0,0 +1,337 @@
+use log::{debug, info, trace, warn};
+use serde::Serialize;
+use crate::data_structure::graphic::{GraphicHeader, GraphicInfo};
+use crate::features::ArgParse;
+use crate::features::sink::Sink;
+use crate::resource::archive::Archive;
+
+const STYLE: &str = r#"body { font-family: sans-serif; margin: 0; background: #222; color: #ddd; }
+a { color: #8cf; }
+header { display: flex; gap: 1em; align-items: center; padding: .5em 1em; background: #111; position: sticky; top: 0; }
+header h1 { font-size: 1.2em; margin: 0; }
+main { padding: 1em; }
+.grid { display: flex; flex-wrap: wrap; gap: .5em; }
+.card { display: flex; flex-direction: column; align-items: center; justify-content: space-between; width: 96px; height: 120px; padding: 4px; background: #333; border: 2px solid #333; text-decoration: none; color: #ddd; }
+.card img { max-width: 96px; max-height: 80px; image-rendering: pixelated; }
+.card.mismatch { border-color: #d33; }
+.card.invalid { border-color: #dd3; }
+.badge { font-size: .7em; padding: 0 .3em; border-radius: 3px; color: #000; }
+.badge.mismatch { background: #d33; }
+.badge.invalid { background: #dd3; }
+.badge.unsupported { background: #999; }
+.pages { margin: 1em 0; }
+.pages a, .pages span { margin-right: .5em; }
+.detail img { image-rendering: pixelated; background: repeating-conic-gradient(#444 0 25%, #555 0 50%) 0 0 / 16px 16px; }
+table { border-collapse: collapse; margin-top: 1em; }
+td, th { border: 1px solid #555; padding: .2em .6em; text-align: left; }
+td.mismatch { color: #d33; font-weight: bold; }
+"#;
+
+const SCRIPT: &str = r#"(function () {
+    var root = document.body.dataset.root;
+    var select = document.getElementById('palette');
+    var palette = localStorage.getItem('palette');
+    if (PALETTES.indexOf(palette) < 0) {
+        palette = PALETTES[0];
+    }
+
+    function apply() {
+        document.querySelectorAll('img[data-id]').forEach(function (img) {
+            img.src = root + 'images/' + encodeURIComponent(palette) + '/' + img.dataset.id + '.png';
+        });
+    }
+
+    PALETTES.forEach(function (name) {
+        var option = document.createElement('option');
+        option.value = option.textContent = name;
+        option.selected = name === palette;
+        select.appendChild(option);
+    });
+    select.addEventListener('change', function () {
+        palette = select.value;
+        localStorage.setItem('palette', palette);
+        apply();
+    });
+    apply();
+
+    // 以 id 或地圖編號搜尋，可使用 id:123 或 map:456 限定欄位
+    var search = document.getElementById('search');
+    if (!search || typeof GRAPHICS === 'undefined') {
+        return;
+    }
+    var pages = document.getElementById('pages');
+    var results = document.getElementById('results');
+    search.addEventListener('input', function () {
+        var query = search.value.trim();
+        if (query === '') {
+            pages.hidden = false;
+            results.hidden = true;
+            return;
+        }
+
+        var field = null;
+        var match = /^(id|map):\s*(\d+)$/.exec(query);
+        if (match) {
+            field = match[1];
+            query = match[2];
+        }
+        var found = GRAPHICS.filter(function (g) {
+            return (field !== 'map' && String(g.id) === query) || (field !== 'id' && String(g.map) === query);
+        });
+
+        results.innerHTML = '';
+        found.slice(0, 500).forEach(function (g) {
+            results.insertAdjacentHTML('beforeend', card(g));
+        });
+        if (found.length > 500) {
+            results.insertAdjacentHTML('beforeend', '<p>' + (found.length - 500) + ' more...</p>');
+        }
+        pages.hidden = true;
+        results.hidden = false;
+        apply();
+    });
+
+    function card(g) {
+        var classes = (g.mismatch ? ' mismatch' : '') + (g.invalid ? ' invalid' : '');
+        var img = g.image ? '<img data-id="' + g.id + '" alt="' + g.id + '">' : '<span>no image</span>';
+        return '<a class="card' + classes + '" href="graphics/' + g.id + '.html">' + img + '<span>' + g.id + '</span></a>';
+    }
+}());
+"#;
+
+// 搜尋用的索引，寫入 graphics.js
+#[derive(Debug, Serialize)]
+struct Entry {
+    id: u32,
+    map: u32,
+    width: u32,
+    height: u32,
+    mismatch: bool,
+    invalid: bool,
+    unsupported: bool,
+    image: bool,
+}
+
+struct Graphic<'a> {
+    info: &'a GraphicInfo,
+    header: GraphicHeader,
+    entry: Entry,
+}
+
+pub fn build_gallery(
+    args: &clap::ArgMatches,
+    archive: &Archive,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    info!("Parsing sub command arguments");
+    let result = ArgParse::parse(args)?;
+    info!("Parsed sub command arguments");
+    debug!("{:?}", result);
+
+    let page_size = result.page_size.unwrap() as usize;
+    if page_size == 0 {
+        return Err("The page size should be greater than 0".into());
+    }
+    let mut sink = Sink::directory(result.output.ok_or("No output chosen, please specify --output")?);
+    let palettes = archive.palettes();
+
+    info!("Rendering graphics with {} palettes", palettes.len());
+    let mut graphics = vec![];
+    for (info, header) in archive.iter_headers() {
+        trace!("Graphic ID: {}", info.id);
+        let header = match header {
+            Ok(header) => header,
+            Err(e) => {
+                warn!("Failed to read the header (id: {}): {}", info.id, e);
+                continue;
+            },
+        };
+        let mut entry = Entry {
+            id: info.id,
+            map: info.map,
+            width: info.width,
+            height: info.height,
+            mismatch: info != &header,
+            invalid: !info.valid_perimeter(),
+            // 圖片版本 > 2 的功能尚未完成，僅顯示資訊
+            unsupported: header.version > 1,
+            image: false,
+        };
+
+        if !entry.unsupported && !entry.invalid {
+            for (name, palette) in &palettes {
+                let image = match archive.render(info.id, palette) {
+                    Ok(Some(image)) => image,
+                    Ok(None) => break,
+                    Err(e) => {
+                        warn!("Failed to render the graphic (id: {}): {}", info.id, e);
+                        break;
+                    },
+                };
+                let mut png = vec![];
+                image.to_png(&mut png)?;
+                sink.write(&format!("images/{}/{}.png", name, info.id), &png)?;
+                entry.image = true;
+            }
+        }
+
+        graphics.push(Graphic {info, header, entry});
+    }
+
+    let names: Vec<&str> = palettes.iter().map(|(name, _)| *name).collect();
+    sink.write("style.css", STYLE.as_bytes())?;
+    sink.write("gallery.js", SCRIPT.as_bytes())?;
+    sink.write("palettes.js", format!("var PALETTES = {};\n", serde_json::to_string(&names)?).as_bytes())?;
+    let entries: Vec<&Entry> = graphics.iter().map(|g| &g.entry).collect();
+    sink.write("graphics.js", format!("var GRAPHICS = {};\n", serde_json::to_string(&entries)?).as_bytes())?;
+
+    let chunks: Vec<&[Graphic]> = graphics.chunks(page_size).collect();
+    let total = chunks.len().max(1);
+    for page in 0..total {
+        let cards = chunks.get(page).map_or_else(String::new, |chunk| chunk.iter().map(|g| card(&g.entry)).collect());
+        sink.write(&page_name(page), index_page(page, total, &cards).as_bytes())?;
+    }
+    for (i, graphic) in graphics.iter().enumerate() {
+        let prev = i.checked_sub(1).map(|i| graphics[i].info.id);
+        let next = graphics.get(i + 1).map(|g| g.info.id);
+        sink.write(&format!("graphics/{}.html", graphic.info.id), detail_page(graphic, prev, next, i / page_size).as_bytes())?;
+    }
+    sink.finish()?;
+    info!("Generated the gallery of {} graphics in {} pages", graphics.len(), total);
+
+    Ok(())
+}
+
+fn page_name(page: usize) -> String {
+    if page == 0 { "index.html".to_string() } else { format!("page_{}.html", page + 1) }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+fn layout(title: &str, root: &str, scripts: &[&str], body: &str) -> String {
+    // 只有列表頁載入搜尋索引
+    let search = if scripts.contains(&"graphics.js") {
+        "<input id=\"search\" type=\"search\" placeholder=\"id or map number\">"
+    } else {
+        ""
+    };
+    let scripts: String = scripts.iter().map(|s| format!("<script src=\"{}{}\"></script>\n", root, s)).collect();
+
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n<link rel=\"stylesheet\" href=\"{root}style.css\">\n</head>\n\
+         <body data-root=\"{root}\">\n<header><h1><a href=\"{root}index.html\">xgate-tool</a></h1>\
+         <label>Palette <select id=\"palette\"></select></label>{search}</header>\n<main>\n{body}\n</main>\n{scripts}</body>\n</html>\n",
+        title = escape(title),
+        root = root,
+        search = search,
+        body = body,
+        scripts = scripts,
+    )
+}
+
+fn badges(entry: &Entry) -> String {
+    let mut ret = String::new();
+    if entry.mismatch {
+        ret.push_str("<span class=\"badge mismatch\">mismatch</span>");
+    }
+    if entry.invalid {
+        ret.push_str("<span class=\"badge invalid\">invalid perimeter</span>");
+    }
+    if entry.unsupported {
+        ret.push_str("<span class=\"badge unsupported\">unsupported</span>");
+    }
+
+    ret
+}
+
+fn card(entry: &Entry) -> String {
+    let mut classes = String::from("card");
+    if entry.mismatch {
+        classes.push_str(" mismatch");
+    }
+    if entry.invalid {
+        classes.push_str(" invalid");
+    }
+    let image = if entry.image {
+        format!("<img data-id=\"{}\" alt=\"{}\">", entry.id, entry.id)
+    } else {
+        "<span>no image</span>".to_string()
+    };
+
+    format!("<a class=\"{}\" href=\"graphics/{}.html\">{}<span>{}</span>{}</a>\n", classes, entry.id, image, entry.id, badges(entry))
+}
+
+fn pagination(page: usize, total: usize) -> String {
+    let mut ret = String::from("<nav class=\"pages\">");
+    for i in 0..total {
+        if i == page {
+            ret.push_str(&format!("<span>{}</span>", i + 1));
+        } else {
+            ret.push_str(&format!("<a href=\"{}\">{}</a>", page_name(i), i + 1));
+        }
+    }
+    ret.push_str("</nav>");
+
+    ret
+}
+
+fn index_page(page: usize, total: usize, cards: &str) -> String {
+    let body = format!(
+        "<div id=\"pages\">{nav}<div class=\"grid\">\n{cards}</div>{nav}</div>\n<div id=\"results\" class=\"grid\" hidden></div>",
+        nav = pagination(page, total),
+        cards = cards,
+    );
+
+    layout(&format!("Graphics - page {}", page + 1), "", &["palettes.js", "graphics.js", "gallery.js"], &body)
+}
+
+fn detail_page(graphic: &Graphic, prev: Option<u32>, next: Option<u32>, page: usize) -> String {
+    let (info, header, entry) = (graphic.info, &graphic.header, &graphic.entry);
+    let image = if entry.image {
+        format!("<img data-id=\"{}\" alt=\"{}\" width=\"{}\" height=\"{}\">", info.id, info.id, info.width * 4, info.height * 4)
+    } else {
+        "<p>no image</p>".to_string()
+    };
+
+    // 只比較兩個檔案共有的欄位
+    let field = |name: &str, info_value: String, header_value: String| {
+        let shared = info_value != "-" && header_value != "-";
+        let class = if shared && info_value != header_value { " class=\"mismatch\"" } else { "" };
+        format!("<tr><th>{}</th><td{}>{}</td><td{}>{}</td></tr>\n", name, class, info_value, class, header_value)
+    };
+    let mut rows = String::new();
+    rows.push_str(&field("id", info.id.to_string(), "-".into()));
+    rows.push_str(&field("address", info.address.to_string(), "-".into()));
+    rows.push_str(&field("length", info.length.to_string(), header.length.to_string()));
+    rows.push_str(&field("width", info.width.to_string(), header.width.to_string()));
+    rows.push_str(&field("height", info.height.to_string(), header.height.to_string()));
+    rows.push_str(&field("offset_x", info.offset_x.to_string(), "-".into()));
+    rows.push_str(&field("offset_y", info.offset_y.to_string(), "-".into()));
+    rows.push_str(&field("tile_east", info.tile_east.to_string(), "-".into()));
+    rows.push_str(&field("tile_south", info.tile_south.to_string(), "-".into()));
+    rows.push_str(&field("access", info.access.to_string(), "-".into()));
+    rows.push_str(&field("unknown", format!("{:?}", info.unknown), "-".into()));
+    rows.push_str(&field("map", info.map.to_string(), "-".into()));
+    rows.push_str(&field("mark", "-".into(), escape(&header.mark.iter().collect::<String>())));
+    rows.push_str(&field("version", "-".into(), header.version.to_string()));
+    rows.push_str(&field("unknown (header)", "-".into(), header.unknown.to_string()));
+
+    let link = |label: &str, id: Option<u32>| match id {
+        Some(id) => format!("<a href=\"{}.html\">{} ({})</a>", id, label, id),
+        None => String::new(),
+    };
+    let body = format!(
+        "<nav class=\"pages\">{prev} <a href=\"../{index}\">back</a> {next}</nav>\n<h2>{id} {badges}</h2>\n<div class=\"detail\">{image}</div>\n\
+         <table>\n<tr><th></th><th>GraphicInfo.bin</th><th>Graphic.bin</th></tr>\n{rows}</table>",
+        prev = link("prev", prev),
+        next = link("next", next),
+        index = page_name(page),
+        id = info.id,
+        badges = badges(entry),
+        image = image,
+        rows = rows,
+    );
+
+    layout(&format!("Graphic {}", info.id), "../", &["palettes.js", "gallery.js"], &body)
+}
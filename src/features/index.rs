@@ -0,0 +1,226 @@
+use std::fs::{self, File};
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+use log::{info, trace, warn};
+use serde::{Serialize, Deserialize};
+use crate::data_structure::graphic::{GraphicHeader, GraphicInfo};
+use crate::features::manifest::{Fingerprint, payload_fingerprint};
+use crate::resource::archive::{self, Archive};
+use crate::resource::graphic::{GraphicInfoResource, GraphicResource};
+
+// 索引格式變更時需遞增，舊的索引會自動重建
+const INDEX_VERSION: u32 = 1;
+// 計算雜湊時只取檔案開頭及結尾，避免每次啟動都讀取整個 <Graphic.bin>
+const SAMPLE_SIZE: u64 = 64 * 1024;
+
+// 來源檔案的識別，大小、修改時間或取樣的雜湊任一不同即視為已變更
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SourceKey {
+    size: u64,
+    modified: (u64, u32),
+    hash: String,
+}
+
+impl SourceKey {
+    pub fn of(path: &str) -> io::Result<Self> {
+        let mut file = File::open(path)?;
+        let metadata = file.metadata()?;
+        let modified = metadata.modified()?.duration_since(UNIX_EPOCH).unwrap_or_default();
+
+        Ok(Self {
+            size: metadata.len(),
+            modified: (modified.as_secs(), modified.subsec_nanos()),
+            hash: sample_hash(&mut file, metadata.len())?,
+        })
+    }
+}
+
+fn sample_hash<R: Read + Seek>(r: &mut R, len: u64) -> io::Result<String> {
+    let mut ret = Fingerprint::new();
+    let mut head = vec![0; len.min(SAMPLE_SIZE) as usize];
+    r.seek(SeekFrom::Start(0))?;
+    r.read_exact(&mut head)?;
+    ret.write(&head);
+
+    if len > SAMPLE_SIZE {
+        let start = (len - SAMPLE_SIZE).max(SAMPLE_SIZE);
+        let mut tail = vec![0; (len - start) as usize];
+        r.seek(SeekFrom::Start(start))?;
+        r.read_exact(&mut tail)?;
+        ret.write(&tail);
+    }
+
+    Ok(ret.hex())
+}
+
+// 保存 <GraphicInfo.bin> 的所有紀錄、各圖片的標頭及內容雜湊，再次執行時不需重新讀取
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ArchiveIndex {
+    version: u32,
+    graphic_info: SourceKey,
+    graphic: SourceKey,
+    infos: Vec<GraphicInfo>,
+    // 以位址對應，讀取失敗的標頭不會被保存
+    headers: Vec<(u32, GraphicHeader)>,
+    // 以 id 對應，與 manifest 的 payload 相同
+    payloads: Vec<(u32, String)>,
+}
+
+impl ArchiveIndex {
+    // 索引不存在、損毀或版本不同時回傳 None
+    pub fn load(path: &Path) -> Option<Self> {
+        let bytes = fs::read(path).ok()?;
+        match bincode::deserialize::<Self>(&bytes) {
+            Ok(index) if index.version == INDEX_VERSION => Some(index),
+            Ok(_) => None,
+            Err(e) => {
+                warn!("Ignored the broken index cache {}: {}", path.display(), e);
+                None
+            },
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, bincode::serialize(self)?)?;
+
+        Ok(())
+    }
+
+    pub fn build(archive: &Archive, graphic_info: SourceKey, graphic: SourceKey) -> Self {
+        info!("Building the index cache of {} graphics", archive.infos().len());
+        let mut headers = vec![];
+        for (info, header) in archive.iter_headers() {
+            if let Ok(header) = header {
+                headers.push((info.address, header));
+            }
+        }
+        let mut payloads = vec![];
+        for (info, graphic) in archive.iter_raw() {
+            trace!("Graphic ID: {}", info.id);
+            // id 重複時與 raw(id) 相同，以第一筆為準
+            if !archive.info(info.id).is_some_and(|first| std::ptr::eq(first, info)) {
+                continue;
+            }
+            if let Ok(hash) = graphic.and_then(|graphic| Ok(payload_fingerprint(info, &graphic)?)) {
+                payloads.push((info.id, hash));
+            }
+        }
+
+        Self {version: INDEX_VERSION, graphic_info, graphic, infos: archive.infos().to_vec(), headers, payloads}
+    }
+
+    pub fn matches(&self, graphic_info: &SourceKey, graphic: &SourceKey) -> bool {
+        &self.graphic_info == graphic_info && &self.graphic == graphic
+    }
+
+    // 以索引中的紀錄取代讀取 <GraphicInfo.bin>
+    pub fn into_archive(self, graphic: GraphicResource) -> Archive {
+        Archive::from_infos(self.infos, graphic).with_index(self.headers, self.payloads)
+    }
+
+    pub fn attach(self, archive: Archive) -> Archive {
+        archive.with_index(self.headers, self.payloads)
+    }
+}
+
+// 索引與來源檔案相符時直接載入，否則重新建立並保存
+pub fn open_indexed(
+    graphic_info: &str,
+    graphic: &str,
+    palette: Option<&str>,
+    path: &Path,
+) -> archive::Result<Archive> {
+    let (info_key, graphic_key) = (SourceKey::of(graphic_info)?, SourceKey::of(graphic)?);
+
+    let archive = match ArchiveIndex::load(path) {
+        Some(index) if index.matches(&info_key, &graphic_key) => {
+            info!("Loaded the index cache from {}", path.display());
+            index.into_archive(GraphicResource::load(graphic)?)
+        },
+        _ => {
+            let archive = Archive::new(GraphicInfoResource::load(graphic_info)?, GraphicResource::load(graphic)?)?;
+            let index = ArchiveIndex::build(&archive, info_key, graphic_key);
+            index.save(path)?;
+            info!("Saved the index cache into {}", path.display());
+            index.attach(archive)
+        },
+    };
+
+    archive.with_palettes(palette)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn sample_head_and_tail() {
+        let len = SAMPLE_SIZE * 3;
+        let data = vec![1u8; len as usize];
+        let hash = |data: &[u8]| sample_hash(&mut Cursor::new(data), data.len() as u64).unwrap();
+
+        let mut tail = data.clone();
+        tail[len as usize - 1] = 2;
+        let mut middle = data.clone();
+        middle[len as usize / 2] = 2;
+
+        assert_ne!(hash(&data), hash(&tail));
+        assert_eq!(hash(&data), hash(&middle));
+        assert_ne!(hash(&[1, 2]), hash(&[2, 1]));
+    }
+
+    #[test]
+    fn round_trip() {
+        let dir = std::env::temp_dir().join(format!("xgate-tool-index-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = |name: &str| dir.join(name).to_string_lossy().into_owned();
+
+        let header = GraphicHeader {mark: ['R', 'D'], version: 0, unknown: 0, width: 2, height: 2, length: 20};
+        let info = GraphicInfo {
+            id: 7, address: 0, length: 20, offset_x: -1, offset_y: -1, width: 2, height: 2,
+            tile_east: 0, tile_south: 0, access: 0, unknown: [0; 5], map: 0,
+        };
+        let mut graphic = bincode::serialize(&header).unwrap();
+        graphic.extend_from_slice(&[1, 2, 3, 4]);
+        fs::write(path("GraphicInfo.bin"), bincode::serialize(&info).unwrap()).unwrap();
+        fs::write(path("Graphic.bin"), &graphic).unwrap();
+        fs::write(path("Empty.bin"), []).unwrap();
+
+        let info_key = SourceKey::of(&path("GraphicInfo.bin")).unwrap();
+        let graphic_key = SourceKey::of(&path("Graphic.bin")).unwrap();
+        let archive = Archive::new(
+            GraphicInfoResource::load(&path("GraphicInfo.bin")).unwrap(),
+            GraphicResource::load(&path("Graphic.bin")).unwrap(),
+        ).unwrap();
+        let index = ArchiveIndex::build(&archive, info_key.clone(), graphic_key.clone());
+        let index_path = dir.join("index.bin");
+        index.save(&index_path).unwrap();
+
+        let loaded = ArchiveIndex::load(&index_path).unwrap();
+        assert!(loaded.matches(&info_key, &graphic_key));
+        let resized = SourceKey {size: graphic_key.size + 1, ..graphic_key.clone()};
+        assert!(!loaded.matches(&info_key, &resized));
+        let touched = SourceKey {modified: (graphic_key.modified.0 + 1, 0), ..graphic_key.clone()};
+        assert!(!loaded.matches(&touched, &graphic_key));
+
+        // 以空檔案取代 <Graphic.bin>，標頭及雜湊仍可由索引取得
+        let hash = payload_fingerprint(&info, &archive.raw(7).unwrap()).unwrap();
+        let indexed = loaded.into_archive(GraphicResource::load(&path("Empty.bin")).unwrap());
+        assert_eq!(header, indexed.header(7).unwrap());
+        assert_eq!(Some(hash.as_str()), indexed.payload_hash(7));
+        assert!(indexed.raw(7).is_err());
+
+        let outdated = ArchiveIndex {version: INDEX_VERSION + 1, ..index};
+        outdated.save(&index_path).unwrap();
+        assert!(ArchiveIndex::load(&index_path).is_none());
+        fs::write(&index_path, [1, 2, 3]).unwrap();
+        assert!(ArchiveIndex::load(&index_path).is_none());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,50 +1,82 @@
-use std::io::SeekFrom;
-use log::{info, debug};
+use log::{info, debug, warn};
 use crate::data_structure::graphic::{GraphicInfo, GraphicHeader};
-use crate::resource::graphic::{GraphicInfoResource, GraphicResource};
+use crate::data_structure::terminal::ColorMode;
+use crate::resource::archive::Archive;
 use crate::features::ArgParse;
 use prettytable::{table, row, cell};
 
-pub fn show_info<T>(args: &clap::ArgMatches, resource: &mut (GraphicInfoResource, GraphicResource, T)) -> Result<(), Box<dyn std::error::Error>>{
+pub fn show_info(args: &clap::ArgMatches, archive: &Archive) -> Result<(), Box<dyn std::error::Error + Send + Sync>>{
     info!("Parsing sub command arguments");
     let result = ArgParse::parse(args)?;
     info!("Parsed sub command arguments");
     debug!("{:?}", result);
 
-    if result.id.is_some() {
-        print_table(vec![find_by_id(result.id.unwrap(),&mut resource.0,&mut resource.1)?], false);
+    if let Some(id) = result.id {
+        let (graphic_info, graphic_header) = find_by_id(id, archive)?;
+        print_table(vec![(graphic_info.clone(), graphic_header.clone())], false);
+        if result.preview {
+            print_preview(&graphic_info, &graphic_header, archive, result.palette_name)?;
+        }
     } else if result.all {
-        print_table(find_all(&mut resource.0, &mut resource.1)?, true);
+        print_table(find_all(archive)?, true);
     }
 
     Ok(())
 }
 
-fn find_by_id(id: u32, graphic_info_resource: &mut GraphicInfoResource, graphic_resource: &mut GraphicResource) -> Result<(GraphicInfo, GraphicHeader), Box<dyn std::error::Error>>{
+fn find_by_id(id: u32, archive: &Archive) -> Result<(GraphicInfo, GraphicHeader), Box<dyn std::error::Error + Send + Sync>>{
     info!("Finding graphic by id = {}", id);
-    let graphic_info = graphic_info_resource.find(|gi| gi.id == id).unwrap();
+    let graphic_header = archive.header(id)?;
+    let graphic_info = archive.info(id).cloned().unwrap();
     debug!("Found graphic_info = {:?}", graphic_info);
-    info!("Finding graphic_header at {}", graphic_info.address);
-    graphic_resource.seek(SeekFrom::Start(graphic_info.address as u64))?;
-    let graphic_header = graphic_resource.read_header()?;
-    debug!("Found graphic_header = {:?}", graphic_header);
 
     Ok((graphic_info, graphic_header))
 }
 
-fn find_all(graphic_info_resource: &mut GraphicInfoResource, graphic_resource: &mut GraphicResource) -> Result<Vec<(GraphicInfo, GraphicHeader)>, Box<dyn std::error::Error>> {
+fn find_all(archive: &Archive) -> Result<Vec<(GraphicInfo, GraphicHeader)>, Box<dyn std::error::Error + Send + Sync>> {
     let mut ret = vec![];
 
     info!("Collecting all of GraphicInfo and GraphicHeader");
-    for graphic_info in graphic_info_resource {
-        graphic_resource.seek(SeekFrom::Start(graphic_info.address as u64))?;
-        ret.push((graphic_info, graphic_resource.read_header()?));
+    for (graphic_info, graphic_header) in archive.iter_headers() {
+        ret.push((graphic_info.clone(), graphic_header?));
     }
     info!("Collected all of GraphicInfo and GraphicHeader");
 
     Ok(ret)
 }
 
+// 與 dump 相同，以選擇的調色盤轉換後顯示於終端機，大小不超過終端機的畫面
+fn print_preview(
+    info: &GraphicInfo,
+    header: &GraphicHeader,
+    archive: &Archive,
+    palette_name: Option<&str>,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    // 圖片版本 > 2 的功能尚未完成，先行跳過
+    if header.version > 1 {
+        warn!("Unsupported graphic version (id: {})", info.id);
+        return Ok(());
+    }
+    let palette = match palette_name {
+        Some(name) => archive.find_palette(name).ok_or_else(|| format!("Palette not found: {}", name))?,
+        None => archive.palette(),
+    };
+    let image = match archive.render(info.id, palette)? {
+        Some(image) => image,
+        None => {
+            warn!("Empty Graphic Data (id: {})", info.id);
+            return Ok(());
+        },
+    };
+
+    let (columns, rows) = crossterm::terminal::size().unwrap_or((80, 24));
+    for line in image.to_half_blocks(columns as u32, rows.saturating_sub(1) as u32, ColorMode::detect()) {
+        println!("{}", line);
+    }
+
+    Ok(())
+}
+
 fn print_table(data: Vec<(GraphicInfo, GraphicHeader)>, skip_equal: bool) {
     let mut table = table!(["id", "GraphicInfo.bin", "Graphic.bin"]);
 
@@ -59,4 +91,4 @@ fn print_table(data: Vec<(GraphicInfo, GraphicHeader)>, skip_equal: bool) {
     }
 
     table.printstd();
-}
\ No newline at end of file
+}
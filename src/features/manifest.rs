@@ -0,0 +1,160 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+use log::{debug, warn};
+use serde::{Serialize, Deserialize};
+use crate::data_structure::graphic::{Graphic, GraphicInfo, Palette};
+
+pub const MANIFEST_NAME: &str = "manifest.json";
+
+// 64-bit FNV-1a，需在不同版本間保持穩定，因此不使用 DefaultHasher
+#[derive(Debug, Clone, Copy)]
+pub struct Fingerprint(u64);
+
+impl Fingerprint {
+    pub fn new() -> Self {
+        Fingerprint(0xcbf2_9ce4_8422_2325)
+    }
+
+    pub fn write(&mut self, bytes: &[u8]) -> &mut Self {
+        for &b in bytes {
+            self.0 ^= b as u64;
+            self.0 = self.0.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+        // 以長度分隔每段資料，避免 "ab" + "c" 與 "a" + "bc" 相同
+        for &b in &(bytes.len() as u64).to_le_bytes() {
+            self.0 ^= b as u64;
+            self.0 = self.0.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+        self
+    }
+
+    pub fn hex(&self) -> String {
+        format!("{:016x}", self.0)
+    }
+}
+
+impl Default for Fingerprint {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// 圖片的來源資料，位址不列入計算，僅搬移位置的圖片不需重新匯出
+pub fn payload_fingerprint(info: &GraphicInfo, graphic: &Graphic) -> Result<String, Box<bincode::ErrorKind>> {
+    let info = GraphicInfo {address: 0, ..info.clone()};
+
+    Ok(Fingerprint::new()
+        .write(&bincode::serialize(&info)?)
+        .write(&bincode::serialize(&graphic.header)?)
+        .write(graphic.data.bytes())
+        .hex())
+}
+
+pub fn palette_fingerprint(palettes: &[(&str, &Palette)]) -> String {
+    let mut ret = Fingerprint::new();
+    for (name, palette) in palettes {
+        ret.write(name.as_bytes()).write(&palette.to_cgp());
+    }
+
+    ret.hex()
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    pub payload: String,
+    pub palette: String,
+    pub options: String,
+    // 相對於輸出目錄的路徑
+    pub files: Vec<String>,
+}
+
+// 記錄輸出目錄中每張圖片的來源，用於略過未變更的圖片
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Manifest {
+    entries: BTreeMap<u32, ManifestEntry>,
+}
+
+impl Manifest {
+    pub fn load(output: &Path) -> Result<Self, io::Error> {
+        let path = output.join(MANIFEST_NAME);
+        if !path.exists() {
+            debug!("No manifest in {}", output.display());
+            return Ok(Self::default());
+        }
+
+        match serde_json::from_slice(&fs::read(&path)?) {
+            Ok(manifest) => Ok(manifest),
+            Err(e) => {
+                warn!("Ignoring the broken manifest {}: {}", path.display(), e);
+                Ok(Self::default())
+            },
+        }
+    }
+
+    // 先寫入暫存檔再取代，中斷時不會留下寫到一半的 manifest
+    pub fn save(&self, output: &Path) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        fs::create_dir_all(output)?;
+        let temp = output.join(format!("{}.tmp", MANIFEST_NAME));
+        fs::write(&temp, serde_json::to_vec_pretty(self)?)?;
+        fs::rename(&temp, output.join(MANIFEST_NAME))?;
+
+        Ok(())
+    }
+
+    // 來源、調色盤及匯出選項皆相同，且輸出的檔案仍存在
+    pub fn is_fresh(&self, id: u32, payload: &str, palette: &str, options: &str, output: &Path) -> bool {
+        match self.entries.get(&id) {
+            Some(entry) => {
+                entry.payload == payload &&
+                entry.palette == palette &&
+                entry.options == options &&
+                entry.files.iter().all(|file| output.join(file).exists())
+            },
+            None => false,
+        }
+    }
+
+    pub fn ids(&self) -> Vec<u32> {
+        self.entries.keys().copied().collect()
+    }
+
+    pub fn get(&self, id: u32) -> Option<&ManifestEntry> {
+        self.entries.get(&id)
+    }
+
+    pub fn insert(&mut self, id: u32, entry: ManifestEntry) -> Option<ManifestEntry> {
+        self.entries.insert(id, entry)
+    }
+
+    pub fn remove(&mut self, id: u32) -> Option<ManifestEntry> {
+        self.entries.remove(&id)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn fnv1a_fingerprint() {
+        assert_eq!("cbf29ce484222325", Fingerprint(0xcbf2_9ce4_8422_2325).hex());
+        assert_eq!(Fingerprint::new().write(b"ab").write(b"c").hex(), Fingerprint::new().write(b"ab").write(b"c").hex());
+        assert_ne!(Fingerprint::new().write(b"ab").write(b"c").hex(), Fingerprint::new().write(b"a").write(b"bc").hex());
+    }
+
+    #[test]
+    fn fresh_entry() {
+        let output = Path::new("/nonexistent");
+        let mut manifest = Manifest::default();
+        manifest.insert(1, ManifestEntry {payload: "a".into(), palette: "b".into(), options: "c".into(), files: vec![]});
+
+        assert!(manifest.is_fresh(1, "a", "b", "c", output));
+        assert!(!manifest.is_fresh(1, "a", "b", "d", output));
+        assert!(!manifest.is_fresh(2, "a", "b", "c", output));
+
+        manifest.insert(1, ManifestEntry {payload: "a".into(), palette: "b".into(), options: "c".into(), files: vec!["1.bmp".into()]});
+        assert!(!manifest.is_fresh(1, "a", "b", "c", output));
+    }
+}
@@ -1,11 +1,77 @@
 pub mod info;
 pub mod dump;
+pub mod palette;
+pub mod template;
+pub mod manifest;
+pub mod sink;
+pub mod index;
+pub mod config;
+#[cfg(feature = "png")]
+pub mod atlas;
+#[cfg(feature = "png")]
+pub mod gallery;
+#[cfg(feature = "png")]
+pub mod serve;
+#[cfg(feature = "png")]
+pub mod browse;
+#[cfg(feature = "png")]
+pub mod montage;
+#[cfg(feature = "png")]
+pub mod find;
 
 #[derive(Debug)]
 pub struct ArgParse<'a> {
     id: Option<u32>,
     all: bool,
     output: Option<&'a str>,
+    format: Option<&'a str>,
+    palette_name: Option<&'a str>,
+    all_palettes: bool,
+    input: Option<&'a str>,
+    indexed: bool,
+    raw: bool,
+    compressed: bool,
+    sidecar: Option<&'a str>,
+    name_template: Option<&'a str>,
+    force: bool,
+    prune: bool,
+    archive: Option<&'a str>,
+    metadata: Option<&'a str>,
+    #[cfg(feature = "png")]
+    range: Option<(u32, u32)>,
+    #[cfg(feature = "png")]
+    map: Option<u32>,
+    #[cfg(feature = "png")]
+    frames: Option<Vec<u32>>,
+    #[cfg(feature = "png")]
+    max_size: Option<u32>,
+    #[cfg(feature = "png")]
+    padding: Option<u32>,
+    #[cfg(feature = "png")]
+    name: Option<&'a str>,
+    trim: bool,
+    canvas: Option<(u32, u32)>,
+    origin: Option<(i32, i32)>,
+    debug_anchor: bool,
+    scale: Option<u32>,
+    scale_filter: Option<&'a str>,
+    #[cfg(feature = "png")]
+    page_size: Option<u32>,
+    #[cfg(feature = "png")]
+    host: Option<&'a str>,
+    #[cfg(feature = "png")]
+    port: Option<u16>,
+    preview: bool,
+    #[cfg(feature = "png")]
+    cell: Option<(u32, u32)>,
+    #[cfg(feature = "png")]
+    columns: Option<u32>,
+    #[cfg(feature = "png")]
+    index: Option<&'a str>,
+    #[cfg(feature = "png")]
+    rebuild: bool,
+    #[cfg(feature = "png")]
+    limit: Option<u32>,
 }
 
 impl<'a> ArgParse<'a> {
@@ -22,6 +88,125 @@ impl<'a> ArgParse<'a> {
             Some(args.value_of("output").unwrap())
         };
 
-        Ok(Self{id, all, output})
+        let format = args.value_of("format");
+        let palette_name = args.value_of("palette_name");
+        let all_palettes = args.is_present("all_palettes");
+        let input = args.value_of("input");
+        let indexed = args.is_present("indexed");
+        let raw = args.is_present("raw");
+        let compressed = args.is_present("compressed");
+        let sidecar = args.value_of("sidecar");
+        let name_template = args.value_of("name_template");
+        let force = args.is_present("force");
+        let prune = args.is_present("prune");
+        let archive = args.value_of("archive");
+        let metadata = args.value_of("metadata");
+        // 範圍的格式為 <start>-<end>（包含兩端），或單一 id
+        #[cfg(feature = "png")]
+        let range = match args.value_of("range") {
+            Some(range) => match range.split_once('-') {
+                Some((start, end)) => Some((start.trim().parse::<u32>()?, end.trim().parse::<u32>()?)),
+                None => Some((range.trim().parse::<u32>()?, range.trim().parse::<u32>()?)),
+            },
+            None => None,
+        };
+        #[cfg(feature = "png")]
+        let map = match args.value_of("map") {
+            Some(map) => Some(map.parse::<u32>()?),
+            None => None,
+        };
+        #[cfg(feature = "png")]
+        let frames = match args.value_of("frames") {
+            Some(frames) => Some(frames.split(',').map(|id| id.trim().parse::<u32>()).collect::<Result<Vec<_>, _>>()?),
+            None => None,
+        };
+        #[cfg(feature = "png")]
+        let max_size = match args.value_of("max_size") {
+            Some(size) => Some(size.parse::<u32>()?),
+            None => None,
+        };
+        #[cfg(feature = "png")]
+        let padding = match args.value_of("padding") {
+            Some(padding) => Some(padding.parse::<u32>()?),
+            None => None,
+        };
+        #[cfg(feature = "png")]
+        let name = args.value_of("name");
+        let trim = args.is_present("trim");
+        // 畫布大小的格式為 <width>x<height>，或單一數字表示正方形
+        let canvas = match args.value_of("canvas") {
+            Some(canvas) => match canvas.split_once('x') {
+                Some((width, height)) => Some((width.trim().parse::<u32>()?, height.trim().parse::<u32>()?)),
+                None => Some((canvas.trim().parse::<u32>()?, canvas.trim().parse::<u32>()?)),
+            },
+            None => None,
+        };
+        let origin = match args.value_of("origin") {
+            Some(origin) => match origin.split_once(',') {
+                Some((x, y)) => Some((x.trim().parse::<i32>()?, y.trim().parse::<i32>()?)),
+                None => Some((origin.trim().parse::<i32>()?, origin.trim().parse::<i32>()?)),
+            },
+            None => None,
+        };
+        let debug_anchor = args.is_present("debug_anchor");
+        let scale = match args.value_of("scale") {
+            Some(scale) => Some(scale.parse::<u32>()?),
+            None => None,
+        };
+        let scale_filter = args.value_of("scale_filter");
+        #[cfg(feature = "png")]
+        let page_size = match args.value_of("page_size") {
+            Some(size) => Some(size.parse::<u32>()?),
+            None => None,
+        };
+        #[cfg(feature = "png")]
+        let host = args.value_of("host");
+        #[cfg(feature = "png")]
+        let port = match args.value_of("port") {
+            Some(port) => Some(port.parse::<u16>()?),
+            None => None,
+        };
+        let preview = args.is_present("preview");
+        #[cfg(feature = "png")]
+        let cell = match args.value_of("cell_size") {
+            Some(cell) => match cell.split_once('x') {
+                Some((width, height)) => Some((width.trim().parse::<u32>()?, height.trim().parse::<u32>()?)),
+                None => Some((cell.trim().parse::<u32>()?, cell.trim().parse::<u32>()?)),
+            },
+            None => None,
+        };
+        #[cfg(feature = "png")]
+        let columns = match args.value_of("columns") {
+            Some(columns) => Some(columns.parse::<u32>()?),
+            None => None,
+        };
+        #[cfg(feature = "png")]
+        let index = args.value_of("index");
+        #[cfg(feature = "png")]
+        let rebuild = args.is_present("rebuild");
+        #[cfg(feature = "png")]
+        let limit = match args.value_of("limit") {
+            Some(limit) => Some(limit.parse::<u32>()?),
+            None => None,
+        };
+
+        Ok(Self{
+            id, all, output, format, palette_name, all_palettes, input, indexed, raw, compressed, sidecar, name_template,
+            force, prune, archive, metadata, trim, canvas, origin, debug_anchor, scale, scale_filter, preview,
+            #[cfg(feature = "png")] range,
+            #[cfg(feature = "png")] map,
+            #[cfg(feature = "png")] frames,
+            #[cfg(feature = "png")] max_size,
+            #[cfg(feature = "png")] padding,
+            #[cfg(feature = "png")] name,
+            #[cfg(feature = "png")] page_size,
+            #[cfg(feature = "png")] host,
+            #[cfg(feature = "png")] port,
+            #[cfg(feature = "png")] cell,
+            #[cfg(feature = "png")] columns,
+            #[cfg(feature = "png")] index,
+            #[cfg(feature = "png")] rebuild,
+            #[cfg(feature = "png")] limit,
+        })
     }
 }
\ No newline at end of file
@@ -0,0 +1,254 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use log::{debug, info, trace, warn};
+use crate::data_structure::graphic::{GraphicInfo, Palette};
+use crate::data_structure::image::{Rect, RgbaImage};
+use crate::features::ArgParse;
+use crate::features::atlas::select_infos;
+use crate::resource::archive::Archive;
+
+// 每頁的邊長上限，避免一次配置過大的記憶體
+const MAX_SIZE: u32 = 8192;
+const GAP: u32 = 1;
+const CAPTION: u32 = 7;
+const BACKGROUND: [u8; 4] = [0x20, 0x20, 0x20, 0xff];
+const CELL: [u8; 4] = [0x40, 0x40, 0x40, 0xff];
+const WHITE: [u8; 4] = [0xff, 0xff, 0xff, 0xff];
+const GRAY: [u8; 4] = [0x90, 0x90, 0x90, 0xff];
+// 與 info 的表格相同：圖片過大為黃色，兩個檔案不一致為紅色
+const YELLOW: [u8; 4] = [0xff, 0xd0, 0x00, 0xff];
+const RED: [u8; 4] = [0xff, 0x30, 0x30, 0xff];
+
+// 3x5 的數字字型，每列以 3 個位元表示
+const DIGITS: [[u8; 5]; 10] = [
+    [0b111, 0b101, 0b101, 0b101, 0b111],
+    [0b010, 0b110, 0b010, 0b010, 0b111],
+    [0b111, 0b001, 0b111, 0b100, 0b111],
+    [0b111, 0b001, 0b111, 0b001, 0b111],
+    [0b101, 0b101, 0b111, 0b001, 0b001],
+    [0b111, 0b100, 0b111, 0b001, 0b111],
+    [0b111, 0b100, 0b111, 0b101, 0b111],
+    [0b111, 0b001, 0b001, 0b001, 0b001],
+    [0b111, 0b101, 0b111, 0b101, 0b111],
+    [0b111, 0b101, 0b111, 0b001, 0b111],
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Mark {
+    Normal,
+    Mismatch,
+    Invalid,
+    Unsupported,
+    Empty,
+    Broken,
+}
+
+pub fn build_montage(
+    args: &clap::ArgMatches,
+    archive: &Archive,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    info!("Parsing sub command arguments");
+    let result = ArgParse::parse(args)?;
+    info!("Parsed sub command arguments");
+    debug!("{:?}", result);
+
+    let output = result.output.ok_or("No output chosen, please specify --output")?;
+    let palette = match result.palette_name {
+        Some(name) => archive.find_palette(name).ok_or_else(|| format!("Palette not found: {}", name))?,
+        None => archive.palette(),
+    };
+    let infos = select_infos(&result, archive)?;
+    if infos.is_empty() {
+        return Err("No graphics selected".into());
+    }
+    let (cell_width, cell_height) = result.cell.unwrap();
+    if cell_width == 0 || cell_height == 0 {
+        return Err("The cell size should be greater than 0".into());
+    }
+
+    // 超過上限的部分分頁輸出，每頁的大小不會溢位
+    let too_large = || format!(
+        "The cell size should not be greater than {}x{}", MAX_SIZE - 2 * GAP, MAX_SIZE - CAPTION - 2 * GAP,
+    );
+    let column_width = cell_width.checked_add(GAP).ok_or_else(too_large)?;
+    let row_height = cell_height.checked_add(CAPTION + GAP).ok_or_else(too_large)?;
+    let (max_columns, max_rows) = ((MAX_SIZE - GAP) / column_width, (MAX_SIZE - GAP) / row_height);
+    if max_columns == 0 || max_rows == 0 {
+        return Err(too_large().into());
+    }
+    let columns = (result.columns.unwrap() as usize).clamp(1, infos.len());
+    if columns > max_columns as usize {
+        warn!("Only {} columns fit in a sheet of {} pixels", max_columns, MAX_SIZE);
+    }
+    let columns = columns.min(max_columns as usize);
+    let chunks: Vec<&[&GraphicInfo]> = infos.chunks(columns * max_rows as usize).collect();
+
+    for (page, chunk) in chunks.iter().enumerate() {
+        let sheet = draw_sheet(chunk, columns, (cell_width, cell_height), archive, palette);
+        let mut png = vec![];
+        sheet.to_png(&mut png)?;
+        let path = if chunks.len() > 1 { page_path(Path::new(output), page) } else { PathBuf::from(output) };
+        fs::write(&path, png)?;
+        info!("Laid out {} graphics in {}x{} cells into {}", chunk.len(), columns, chunk.len().div_ceil(columns), path.display());
+    }
+
+    Ok(())
+}
+
+fn draw_sheet(
+    infos: &[&GraphicInfo],
+    columns: usize,
+    (cell_width, cell_height): (u32, u32),
+    archive: &Archive,
+    palette: &Palette,
+) -> RgbaImage {
+    let rows = infos.len().div_ceil(columns);
+    let mut sheet = RgbaImage::new(
+        columns as u32 * (cell_width + GAP) + GAP,
+        rows as u32 * (cell_height + CAPTION + GAP) + GAP,
+    );
+    let whole = Rect {x: 0, y: 0, width: sheet.width, height: sheet.height};
+    fill(&mut sheet, whole, BACKGROUND);
+
+    for (i, info) in infos.iter().enumerate() {
+        trace!("Graphic ID: {}", info.id);
+        let x = (i % columns) as u32 * (cell_width + GAP) + GAP;
+        let y = (i / columns) as u32 * (cell_height + CAPTION + GAP) + GAP;
+        let cell = Rect {x, y, width: cell_width, height: cell_height};
+        fill(&mut sheet, cell, CELL);
+
+        let mark = match draw_cell(&mut sheet, cell, info, archive, palette) {
+            Ok(mark) => mark,
+            Err(e) => {
+                debug!("Failed to render the graphic (id: {}): {}", info.id, e);
+                Mark::Broken
+            },
+        };
+        let color = match mark {
+            Mark::Normal => WHITE,
+            Mark::Mismatch | Mark::Broken => RED,
+            Mark::Invalid => YELLOW,
+            Mark::Unsupported | Mark::Empty => GRAY,
+        };
+        if mark != Mark::Normal {
+            outline(&mut sheet, cell, color);
+        }
+        if ![Mark::Normal, Mark::Mismatch].contains(&mark) {
+            cross(&mut sheet, cell, color);
+        }
+
+        let caption = info.id.to_string();
+        let text_width = (caption.len() as u32 * 4).saturating_sub(1).min(cell_width);
+        text(&mut sheet, x + (cell_width - text_width) / 2, y + cell_height + 1, &caption, cell_width, color);
+    }
+
+    sheet
+}
+
+// 多頁時以 {name}_{page}.png 命名，與 atlas 相同
+fn page_path(path: &Path, page: usize) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let name = match path.extension() {
+        Some(ext) => format!("{}_{}.{}", stem, page, ext.to_string_lossy()),
+        None => format!("{}_{}", stem, page),
+    };
+
+    path.with_file_name(name)
+}
+
+// 圖片縮小至儲存格內並置中，回傳需要標示的狀態
+fn draw_cell(
+    sheet: &mut RgbaImage,
+    cell: Rect,
+    info: &GraphicInfo,
+    archive: &Archive,
+    palette: &Palette,
+) -> Result<Mark, Box<dyn std::error::Error + Send + Sync>> {
+    if !info.valid_perimeter() {
+        return Ok(Mark::Invalid);
+    }
+    let header = archive.header(info.id)?;
+    if header.version > 1 {
+        return Ok(Mark::Unsupported);
+    }
+    let image = match archive.render(info.id, palette)? {
+        Some(image) => image,
+        None => return Ok(Mark::Empty),
+    };
+
+    let factor = image.width.div_ceil(cell.width).max(image.height.div_ceil(cell.height)).max(1);
+    let (w, h) = (image.width.div_ceil(factor), image.height.div_ceil(factor));
+    let (left, top) = (cell.x + (cell.width - w) / 2, cell.y + (cell.height - h) / 2);
+    for dy in 0..h {
+        for dx in 0..w {
+            let rgba = image.get_pixel(dx * factor, dy * factor);
+            if rgba[3] != 0 {
+                sheet.set_pixel(left + dx, top + dy, rgba);
+            }
+        }
+    }
+
+    Ok(if info != &header { Mark::Mismatch } else { Mark::Normal })
+}
+
+fn fill(img: &mut RgbaImage, rect: Rect, rgba: [u8; 4]) {
+    for y in rect.y..rect.y + rect.height {
+        for x in rect.x..rect.x + rect.width {
+            img.set_pixel(x, y, rgba);
+        }
+    }
+}
+
+fn outline(img: &mut RgbaImage, rect: Rect, rgba: [u8; 4]) {
+    let Rect {x, y, width, height} = rect;
+    fill(img, Rect {x, y, width, height: 1}, rgba);
+    fill(img, Rect {x, y: y + height - 1, width, height: 1}, rgba);
+    fill(img, Rect {x, y, width: 1, height}, rgba);
+    fill(img, Rect {x: x + width - 1, y, width: 1, height}, rgba);
+}
+
+fn cross(img: &mut RgbaImage, rect: Rect, rgba: [u8; 4]) {
+    let steps = rect.width.max(rect.height);
+    for i in 0..steps {
+        let (dx, dy) = (i * rect.width / steps, i * rect.height / steps);
+        img.set_pixel(rect.x + dx, rect.y + dy, rgba);
+        img.set_pixel(rect.x + rect.width - 1 - dx, rect.y + dy, rgba);
+    }
+}
+
+// 只支援數字，超過 max_width 的部分會被截斷
+fn text(img: &mut RgbaImage, x: u32, y: u32, digits: &str, max_width: u32, rgba: [u8; 4]) {
+    for (i, digit) in digits.bytes().enumerate() {
+        let glyph = &DIGITS[(digit - b'0') as usize];
+        for (row, bits) in glyph.iter().enumerate() {
+            for column in 0..3 {
+                let dx = i as u32 * 4 + column;
+                if bits & (0b100 >> column) != 0 && dx < max_width {
+                    img.set_pixel(x + dx, y + row as u32, rgba);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn draw_digits() {
+        let mut img = RgbaImage::new(7, 5);
+        text(&mut img, 0, 0, "17", 7, WHITE);
+        let row = |y: u32| (0..7).map(|x| if img.get_pixel(x, y)[3] != 0 { '#' } else { '.' }).collect::<String>();
+
+        assert_eq!(".#..###", row(0));
+        assert_eq!("##....#", row(1));
+        assert_eq!("###...#", row(4));
+    }
+
+    #[test]
+    fn name_pages() {
+        assert_eq!(PathBuf::from("out/sheet_2.png"), page_path(Path::new("out/sheet.png"), 2));
+        assert_eq!(PathBuf::from("sheet_0"), page_path(Path::new("sheet"), 0));
+    }
+}
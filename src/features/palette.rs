@@ -0,0 +1,297 @@
+use std::fs;
+use std::path::Path;
+use log::{debug, info, warn};
+use serde::{Serialize, Deserialize};
+use crate::data_structure::graphic::{Palette, Pixel};
+#[cfg(feature = "png")]
+use crate::data_structure::image::RgbaImage;
+use crate::features::ArgParse;
+use crate::resource::archive::DEFAULT_PALETTE_NAME;
+use crate::resource::graphic::PaletteResource;
+
+// 色票圖中每個顏色的邊長（px），共 16 x 16 格
+#[cfg(feature = "png")]
+const SWATCH_CELL: u32 = 16;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct PaletteJson {
+    pub(crate) name: String,
+    pub(crate) colors: Vec<Pixel>,
+}
+
+pub fn convert_palette(
+    args: &clap::ArgMatches,
+    palette: Option<&str>,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    match args.subcommand() {
+        ("export", Some(sub_args)) => export_palette(sub_args, palette),
+        ("import", Some(sub_args)) => import_palette(sub_args),
+        _ => Ok(()),
+    }
+}
+
+fn export_palette(
+    args: &clap::ArgMatches,
+    palette: Option<&str>,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    info!("Parsing sub command arguments");
+    let result = ArgParse::parse(args)?;
+    info!("Parsed sub command arguments");
+    debug!("{:?}", result);
+
+    let (name, palette) = load_palette(palette, result.palette_name)?;
+    let output = result.output.unwrap();
+    let format = palette_format(result.format, output)?;
+
+    info!("Exporting palette {} as {} to {}", name, format, output);
+    let bytes = match format.as_str() {
+        #[cfg(feature = "png")]
+        "png" => encode_swatch(&palette)?,
+        "gpl" => encode_gpl(&name, &palette),
+        "act" => encode_act(&palette),
+        "json" => serde_json::to_vec_pretty(&PaletteJson {name, colors: padded_colors(&palette)})?,
+        "cgp" => palette.to_cgp(),
+        _ => return Err(format!("Unsupported palette format: {}", format).into()),
+    };
+    fs::write(output, bytes)?;
+    info!("Exported palette");
+
+    Ok(())
+}
+
+fn import_palette(args: &clap::ArgMatches) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    info!("Parsing sub command arguments");
+    let result = ArgParse::parse(args)?;
+    info!("Parsed sub command arguments");
+    debug!("{:?}", result);
+
+    let input = result.input.unwrap();
+    let format = palette_format(result.format, input)?;
+    let bytes = fs::read(input)?;
+
+    info!("Importing palette from {} as {}", input, format);
+    let colors = match format.as_str() {
+        #[cfg(feature = "png")]
+        "png" => decode_swatch(&bytes)?,
+        "gpl" => decode_gpl(&bytes)?,
+        "act" => decode_act(&bytes)?,
+        "json" => serde_json::from_slice::<PaletteJson>(&bytes)?.colors,
+        "cgp" => Palette::new(&bytes).colors().to_vec(),
+        _ => return Err(format!("Unsupported palette format: {}", format).into()),
+    };
+    fs::write(result.output.unwrap(), to_cgp(&colors)?)?;
+    info!("Imported palette");
+
+    Ok(())
+}
+
+fn load_palette(
+    path: Option<&str>,
+    name: Option<&str>,
+) -> Result<(String, Palette), Box<dyn std::error::Error + Send + Sync>> {
+    let mut palettes = PaletteResource::load_all(path)?;
+    if palettes.is_empty() {
+        return Ok((DEFAULT_PALETTE_NAME.to_string(), Palette::default()));
+    }
+
+    let index = match name {
+        Some(name) => match palettes.iter().position(|(n, _)| n == name) {
+            Some(index) => index,
+            None => return Err(format!("Palette not found: {}", name).into()),
+        },
+        None => 0,
+    };
+    let (name, mut resource) = palettes.swap_remove(index);
+
+    Ok((name, resource.build()?))
+}
+
+fn palette_format(format: Option<&str>, path: &str) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+    if let Some(format) = format {
+        return Ok(format.to_string());
+    }
+
+    match Path::new(path).extension().and_then(|ext| ext.to_str()) {
+        Some(ext) => Ok(ext.to_ascii_lowercase()),
+        None => Err(format!("Cannot detect the palette format of {}, please specify --format", path).into()),
+    }
+}
+
+// 完整的調色盤為 256 色，僅中間 224 色可寫入 <Palet.cgp>
+fn to_cgp(colors: &[Pixel]) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>> {
+    let colors = match colors.len() {
+        256 => {
+            let fixed = Palette::default();
+            let fixed = fixed.colors();
+            if colors[..16] != fixed[..16] || colors[240..] != fixed[240..] {
+                warn!("The first and last 16 colors are fixed, changes to them are discarded");
+            }
+            &colors[16..240]
+        },
+        224 => colors,
+        len => return Err(format!("A palette should contain 256 or 224 colors, got {}", len).into()),
+    };
+
+    let mut ret = vec![];
+    for color in colors {
+        ret.append(&mut bincode::serialize(color)?);
+    }
+
+    Ok(ret)
+}
+
+// <Palet.cgp> 過短時調色盤不足 256 色，與繪製圖片時相同，缺少的顏色以黑色補齊
+fn padded_colors(palette: &Palette) -> Vec<Pixel> {
+    let mut ret = palette.colors().to_vec();
+    ret.resize(256, Pixel {b: 0, g: 0, r: 0});
+
+    ret
+}
+
+#[cfg(feature = "png")]
+fn encode_swatch(palette: &Palette) -> Result<Vec<u8>, png::EncodingError> {
+    let colors = padded_colors(palette);
+    let size = SWATCH_CELL * 16;
+    let mut img = RgbaImage::new(size, size);
+    for y in 0..size {
+        for x in 0..size {
+            let color = &colors[((y / SWATCH_CELL) * 16 + x / SWATCH_CELL) as usize];
+            img.set_pixel(x, y, [color.r, color.g, color.b, 0xff]);
+        }
+    }
+
+    let mut ret = vec![];
+    img.to_png(&mut ret)?;
+
+    Ok(ret)
+}
+
+#[cfg(feature = "png")]
+fn decode_swatch(bytes: &[u8]) -> Result<Vec<Pixel>, Box<dyn std::error::Error + Send + Sync>> {
+    let img = RgbaImage::from_png(bytes)?;
+    let (cell_w, cell_h) = (img.width / 16, img.height / 16);
+    if cell_w == 0 || cell_h == 0 {
+        return Err(format!("The swatch should be at least 16x16, got {}x{}", img.width, img.height).into());
+    }
+
+    // 取每一格中心點的顏色
+    let mut ret = vec![];
+    for i in 0..256 {
+        let [r, g, b, _] = img.get_pixel((i % 16) * cell_w + cell_w / 2, (i / 16) * cell_h + cell_h / 2);
+        ret.push(Pixel {b, g, r});
+    }
+
+    Ok(ret)
+}
+
+fn encode_gpl(name: &str, palette: &Palette) -> Vec<u8> {
+    let mut ret = format!("GIMP Palette\nName: {}\nColumns: 16\n#\n", name);
+    for (i, color) in padded_colors(palette).iter().enumerate() {
+        ret.push_str(&format!("{:3} {:3} {:3}\tIndex {}\n", color.r, color.g, color.b, i));
+    }
+
+    ret.into_bytes()
+}
+
+fn decode_gpl(bytes: &[u8]) -> Result<Vec<Pixel>, Box<dyn std::error::Error + Send + Sync>> {
+    let text = String::from_utf8_lossy(bytes);
+    let mut lines = text.lines();
+    if lines.next().map(|line| line.trim()) != Some("GIMP Palette") {
+        return Err("Not a GIMP palette".into());
+    }
+
+    let mut ret = vec![];
+    for line in lines.map(|line| line.trim()) {
+        if line.is_empty() || line.starts_with('#') || line.starts_with("Name:") || line.starts_with("Columns:") {
+            continue;
+        }
+
+        let mut channels = line.split_whitespace().take(3).map(|c| c.parse::<u8>());
+        match (channels.next(), channels.next(), channels.next()) {
+            (Some(r), Some(g), Some(b)) => ret.push(Pixel {b: b?, g: g?, r: r?}),
+            _ => return Err(format!("Invalid color in GIMP palette: {}", line).into()),
+        }
+    }
+
+    Ok(ret)
+}
+
+fn encode_act(palette: &Palette) -> Vec<u8> {
+    let mut ret = vec![];
+    for color in padded_colors(palette) {
+        ret.extend_from_slice(&[color.r, color.g, color.b]);
+    }
+
+    ret
+}
+
+fn decode_act(bytes: &[u8]) -> Result<Vec<Pixel>, Box<dyn std::error::Error + Send + Sync>> {
+    // .act 為 768 bytes 的 RGB，部分版本在後方另有 2 bytes 的顏色數量及 2 bytes 的透明色號
+    let count = match bytes.len() {
+        768 => 256,
+        772 => u16::from_be_bytes([bytes[768], bytes[769]]).min(256) as usize,
+        len => return Err(format!("Invalid size of Photoshop palette: {} bytes", len).into()),
+    };
+
+    Ok(bytes[..count * 3].chunks_exact(3).map(|c| Pixel {b: c[2], g: c[1], r: c[0]}).collect())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn gpl_round_trip() {
+        let palette = Palette::default();
+
+        assert_eq!(palette.colors(), &decode_gpl(&encode_gpl("default", &palette)).unwrap()[..]);
+        assert!(decode_gpl(b"JASC-PAL").is_err());
+    }
+
+    #[test]
+    fn act_round_trip() {
+        let palette = Palette::default();
+        let mut bytes = encode_act(&palette);
+
+        assert_eq!(768, bytes.len());
+        assert_eq!(palette.colors(), &decode_act(&bytes).unwrap()[..]);
+        // 附加顏色數量及透明色號的版本
+        bytes.extend_from_slice(&[0x00, 0x10, 0xff, 0xff]);
+        assert_eq!(&palette.colors()[..16], &decode_act(&bytes).unwrap()[..]);
+        assert!(decode_act(&bytes[..767]).is_err());
+    }
+
+    #[cfg(feature = "png")]
+    #[test]
+    fn swatch_round_trip() {
+        let palette = Palette::default();
+
+        assert_eq!(palette.colors(), &decode_swatch(&encode_swatch(&palette).unwrap()).unwrap()[..]);
+    }
+
+    #[test]
+    fn cgp_round_trip() {
+        let palette = Palette::default();
+        let cgp = palette.to_cgp();
+
+        assert_eq!(224 * 3, cgp.len());
+        assert_eq!(palette.colors(), Palette::new(&cgp).colors());
+        assert_eq!(cgp, to_cgp(palette.colors()).unwrap());
+        assert_eq!(cgp, to_cgp(&palette.colors()[16..240]).unwrap());
+        assert!(to_cgp(&palette.colors()[..100]).is_err());
+    }
+
+    #[test]
+    fn pad_short_palette() {
+        let palette = Palette::new(&[0x10; 30]);
+        assert_eq!(42, palette.colors().len());
+
+        let colors = decode_act(&encode_act(&palette)).unwrap();
+        assert_eq!(256, colors.len());
+        assert_eq!(palette.colors(), &colors[..42]);
+        assert_eq!(Pixel {b: 0, g: 0, r: 0}, colors[255]);
+        assert_eq!(256, decode_gpl(&encode_gpl("short", &palette)).unwrap().len());
+        #[cfg(feature = "png")]
+        assert_eq!(colors, decode_swatch(&encode_swatch(&palette).unwrap()).unwrap());
+    }
+}
@@ -0,0 +1,256 @@
+use std::collections::HashMap;
+use std::thread;
+use log::{debug, info, warn};
+use serde::Serialize;
+use tiny_http::{Header, Method, Request, Response, Server};
+use crate::data_structure::graphic::{GraphicHeader, GraphicInfo};
+use crate::features::ArgParse;
+use crate::features::palette::PaletteJson;
+use crate::resource::archive::Archive;
+
+#[derive(Debug, PartialEq)]
+enum Route {
+    Graphic {id: u32, palette: Option<String>},
+    Info(u32),
+    Search {map: Option<u32>, version: Option<u8>},
+    Palette(Option<String>),
+}
+
+#[derive(Debug)]
+struct Reply {
+    status: u16,
+    content_type: &'static str,
+    body: Vec<u8>,
+}
+
+impl Reply {
+    fn json<T: Serialize>(value: &T) -> Self {
+        match serde_json::to_vec(value) {
+            Ok(body) => Self {status: 200, content_type: "application/json", body},
+            Err(e) => Self::error(500, &e.to_string()),
+        }
+    }
+
+    fn error(status: u16, message: &str) -> Self {
+        #[derive(Serialize)]
+        struct Error<'a> {
+            error: &'a str,
+        }
+
+        Self {
+            status,
+            content_type: "application/json",
+            body: serde_json::to_vec(&Error {error: message}).unwrap_or_default(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct InfoJson<'a> {
+    info: &'a GraphicInfo,
+    header: GraphicHeader,
+}
+
+#[derive(Debug, Serialize)]
+struct PaletteList<'a> {
+    current: &'a str,
+    palettes: Vec<&'a str>,
+}
+
+pub fn serve_archive(
+    args: &clap::ArgMatches,
+    archive: &Archive,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    info!("Parsing sub command arguments");
+    let result = ArgParse::parse(args)?;
+    info!("Parsed sub command arguments");
+    debug!("{:?}", result);
+
+    let address = format!("{}:{}", result.host.unwrap(), result.port.unwrap());
+    let server = Server::http(&address)?;
+    let workers = thread::available_parallelism().map_or(1, |n| n.get());
+    info!("Listening on http://{} with {} workers", address, workers);
+
+    // Archive 可跨執行緒共用，每個 worker 各自接收請求
+    thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| loop {
+                match server.recv() {
+                    Ok(request) => handle(request, archive),
+                    Err(e) => {
+                        warn!("Failed to receive the request: {}", e);
+                        break;
+                    },
+                }
+            });
+        }
+    });
+
+    Ok(())
+}
+
+fn handle(request: Request, archive: &Archive) {
+    let reply = if *request.method() != Method::Get {
+        Reply::error(405, "Only GET is supported")
+    } else {
+        match Route::parse(request.url()) {
+            Ok(route) => respond(route, archive),
+            Err(reply) => reply,
+        }
+    };
+    info!("{} {} {}", request.method(), request.url(), reply.status);
+
+    // 內部的網頁工具與地圖編輯器會由其他來源存取
+    let response = Response::from_data(reply.body)
+        .with_status_code(reply.status)
+        .with_header(Header::from_bytes(&b"Content-Type"[..], reply.content_type.as_bytes()).unwrap())
+        .with_header(Header::from_bytes(&b"Access-Control-Allow-Origin"[..], &b"*"[..]).unwrap());
+    if let Err(e) = request.respond(response) {
+        warn!("Failed to send the response: {}", e);
+    }
+}
+
+impl Route {
+    fn parse(url: &str) -> Result<Self, Reply> {
+        let (path, query) = url.split_once('?').unwrap_or((url, ""));
+        let query = parse_query(query);
+        let param = |name: &str| query.get(name).filter(|value| !value.is_empty());
+        let id = |value: &str| value.parse::<u32>().map_err(|_| Reply::error(400, &format!("Invalid graphic id: {}", value)));
+
+        if let Some(id_png) = path.strip_prefix("/graphic/").and_then(|p| p.strip_suffix(".png")) {
+            return Ok(Route::Graphic {id: id(id_png)?, palette: param("palette").cloned()});
+        }
+        if let Some(id_json) = path.strip_prefix("/info/").and_then(|p| p.strip_suffix(".json")) {
+            return Ok(Route::Info(id(id_json)?));
+        }
+        match path {
+            "/search" => {
+                let map = match param("map") {
+                    Some(map) => Some(map.parse::<u32>().map_err(|_| Reply::error(400, &format!("Invalid map: {}", map)))?),
+                    None => None,
+                };
+                let version = match param("version") {
+                    Some(version) => Some(version.parse::<u8>().map_err(|_| Reply::error(400, &format!("Invalid version: {}", version)))?),
+                    None => None,
+                };
+                Ok(Route::Search {map, version})
+            },
+            "/palette" => Ok(Route::Palette(param("name").cloned())),
+            _ => Err(Reply::error(404, &format!("Not found: {}", path))),
+        }
+    }
+}
+
+fn respond(route: Route, archive: &Archive) -> Reply {
+    match route {
+        Route::Graphic {id, palette} => {
+            if archive.info(id).is_none() {
+                return Reply::error(404, &format!("Graphic not found (id: {})", id));
+            }
+            let palette = match palette {
+                Some(name) => match archive.find_palette(&name) {
+                    Some(palette) => palette,
+                    None => return Reply::error(404, &format!("Palette not found: {}", name)),
+                },
+                None => archive.palette(),
+            };
+            let image = match archive.render(id, palette) {
+                Ok(Some(image)) => image,
+                Ok(None) => return Reply::error(404, &format!("Empty Graphic Data (id: {})", id)),
+                Err(e) => return Reply::error(500, &e.to_string()),
+            };
+            let mut body = vec![];
+            match image.to_png(&mut body) {
+                Ok(()) => Reply {status: 200, content_type: "image/png", body},
+                Err(e) => Reply::error(500, &e.to_string()),
+            }
+        },
+        Route::Info(id) => {
+            let info = match archive.info(id) {
+                Some(info) => info,
+                None => return Reply::error(404, &format!("Graphic not found (id: {})", id)),
+            };
+            match archive.header(id) {
+                Ok(header) => Reply::json(&InfoJson {info, header}),
+                Err(e) => Reply::error(500, &e.to_string()),
+            }
+        },
+        Route::Search {map, version} => {
+            let mut found = vec![];
+            for info in archive.infos() {
+                if map.is_some_and(|map| info.map != map) {
+                    continue;
+                }
+                // 只有指定版本時才需要讀取圖片標頭
+                if let Some(version) = version {
+                    match archive.header(info.id) {
+                        Ok(header) if header.version == version => {},
+                        Ok(_) => continue,
+                        Err(e) => return Reply::error(500, &e.to_string()),
+                    }
+                }
+                found.push(info);
+            }
+            Reply::json(&found)
+        },
+        Route::Palette(None) => Reply::json(&PaletteList {
+            current: archive.palette_name(),
+            palettes: archive.palettes().into_iter().map(|(name, _)| name).collect(),
+        }),
+        Route::Palette(Some(name)) => match archive.find_palette(&name) {
+            Some(palette) => Reply::json(&PaletteJson {name, colors: palette.colors().to_vec()}),
+            None => Reply::error(404, &format!("Palette not found: {}", name)),
+        },
+    }
+}
+
+fn parse_query(query: &str) -> HashMap<&str, String> {
+    query.split('&')
+        .filter_map(|pair| pair.split_once('=').or(Some((pair, ""))))
+        .filter(|(key, _)| !key.is_empty())
+        .map(|(key, value)| (key, decode_component(value)))
+        .collect()
+}
+
+// 解碼 %XX 及 +，無法解碼的部分保持原樣
+fn decode_component(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut ret = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        // from_str_radix 接受正負號，需先確認兩個字元皆為十六進位數字
+        let escaped = value.get(i + 1..i + 3)
+            .filter(|hex| hex.bytes().all(|b| b.is_ascii_hexdigit()))
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[i], escaped) {
+            (b'+', _) => ret.push(b' '),
+            (b'%', Some(byte)) => {
+                ret.push(byte);
+                i += 2;
+            },
+            (byte, _) => ret.push(byte),
+        }
+        i += 1;
+    }
+
+    String::from_utf8_lossy(&ret).into_owned()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_routes() {
+        assert_eq!(Route::Graphic {id: 12, palette: None}, Route::parse("/graphic/12.png").unwrap());
+        assert_eq!(Route::Graphic {id: 12, palette: Some("palet_01".into())}, Route::parse("/graphic/12.png?palette=palet_01").unwrap());
+        assert_eq!(Route::Info(7), Route::parse("/info/7.json?x=1").unwrap());
+        assert_eq!(Route::Search {map: Some(100), version: None}, Route::parse("/search?map=100&version=").unwrap());
+        assert_eq!(Route::Palette(Some("palet 00".into())), Route::parse("/palette?name=palet%2000").unwrap());
+        assert_eq!(Route::Palette(Some("palet% 1".into())), Route::parse("/palette?name=palet%+1").unwrap());
+        assert_eq!(Route::Palette(None), Route::parse("/palette").unwrap());
+        assert_eq!(400, Route::parse("/graphic/abc.png").unwrap_err().status);
+        assert_eq!(400, Route::parse("/search?version=300").unwrap_err().status);
+        assert_eq!(404, Route::parse("/graphic/12.bmp").unwrap_err().status);
+    }
+}
@@ -0,0 +1,85 @@
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use log::{info, trace};
+use zip::{CompressionMethod, ZipWriter};
+use zip::write::FileOptions;
+
+// 匯出檔案的目的地，可為目錄或 zip / tar 封存檔
+pub enum Sink {
+    Directory(PathBuf),
+    Zip(ZipWriter<BufWriter<File>>),
+    Tar(tar::Builder<BufWriter<File>>),
+}
+
+impl Sink {
+    pub fn directory<P: AsRef<Path>>(path: P) -> Self {
+        Sink::Directory(path.as_ref().to_path_buf())
+    }
+
+    // 依副檔名決定封存檔的格式
+    pub fn archive<P: AsRef<Path>>(path: P) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        let path = path.as_ref();
+        let ext = path.extension().and_then(|ext| ext.to_str()).map(|ext| ext.to_ascii_lowercase());
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        info!("Writing into archive {}", path.display());
+        match ext.as_deref() {
+            Some("zip") => Ok(Sink::Zip(ZipWriter::new(BufWriter::new(File::create(path)?)))),
+            Some("tar") => Ok(Sink::Tar(tar::Builder::new(BufWriter::new(File::create(path)?)))),
+            _ => Err(format!("Unsupported archive format: {}, it should be .zip or .tar", path.display()).into()),
+        }
+    }
+
+    // 僅目錄可保留上次匯出的檔案
+    pub fn directory_path(&self) -> Option<&Path> {
+        match self {
+            Sink::Directory(path) => Some(path),
+            _ => None,
+        }
+    }
+
+    // name 為以 / 分隔的相對路徑
+    pub fn write(&mut self, name: &str, bytes: &[u8]) -> Result<(), io::Error> {
+        trace!("Writing {} ({} bytes)", name, bytes.len());
+        match self {
+            Sink::Directory(output) => {
+                let path = output.join(name);
+                if let Some(parent) = path.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                fs::write(path, bytes)
+            },
+            Sink::Zip(zip) => {
+                // PNG 已經過壓縮，不需再壓縮一次
+                let method = if name.ends_with(".png") { CompressionMethod::Stored } else { CompressionMethod::Deflated };
+                zip.start_file(name, FileOptions::default().compression_method(method))?;
+                zip.write_all(bytes)
+            },
+            Sink::Tar(tar) => {
+                let mut header = tar::Header::new_gnu();
+                header.set_size(bytes.len() as u64);
+                header.set_mode(0o644);
+                header.set_mtime(SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0));
+                header.set_cksum();
+                tar.append_data(&mut header, name, bytes)
+            },
+        }
+    }
+
+    // 封存檔需在結束時寫入目錄資訊
+    pub fn finish(self) -> Result<(), io::Error> {
+        match self {
+            Sink::Directory(_) => Ok(()),
+            Sink::Zip(mut zip) => {
+                zip.finish()?.flush()
+            },
+            Sink::Tar(tar) => {
+                tar.into_inner()?.flush()
+            },
+        }
+    }
+}
@@ -0,0 +1,212 @@
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Field {
+    Id,
+    Map,
+    Version,
+    Width,
+    Height,
+    Palette,
+}
+
+#[derive(Debug, PartialEq)]
+enum Part {
+    Literal(String),
+    // 數值欄位可使用 {id/1000} 的形式除以指定的數字，用於分桶
+    Field(Field, Option<u32>),
+}
+
+#[derive(Debug)]
+pub struct NameFields<'a> {
+    pub id: u32,
+    pub map: u32,
+    pub version: u8,
+    pub width: u32,
+    pub height: u32,
+    pub palette: &'a str,
+}
+
+#[derive(Debug)]
+pub struct TemplateError(String);
+
+impl fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid name template: {}", self.0)
+    }
+}
+
+impl std::error::Error for TemplateError {}
+
+// 輸出檔名的樣板，例如 "{map}/{id/1000}/{id}_{width}x{height}"，可用 / 建立子目錄，副檔名會自動加上
+#[derive(Debug, PartialEq)]
+pub struct NameTemplate(Vec<Part>);
+
+impl NameTemplate {
+    pub fn parse(template: &str) -> Result<Self, TemplateError> {
+        let mut parts = vec![];
+        let mut rest = template;
+
+        while let Some(start) = rest.find('{') {
+            if start > 0 {
+                parts.push(Part::Literal(rest[..start].to_string()));
+            }
+            let end = match rest[start..].find('}') {
+                Some(end) => start + end,
+                None => return Err(TemplateError(format!("unclosed '{{' in {}", template))),
+            };
+            parts.push(Self::parse_field(&rest[start + 1..end])?);
+            rest = &rest[end + 1..];
+        }
+        if !rest.is_empty() {
+            parts.push(Part::Literal(rest.to_string()));
+        }
+
+        if parts.is_empty() {
+            return Err(TemplateError("empty template".to_string()));
+        }
+        // 不同圖片的檔名不可相同，{id} 不可除以數字
+        if !parts.contains(&Part::Field(Field::Id, None)) {
+            return Err(TemplateError(format!("{{id}} is required in {}", template)));
+        }
+        Self::check_path(template, &parts)?;
+
+        Ok(NameTemplate(parts))
+    }
+
+    // 檔名不可離開輸出目錄：不可為絕對路徑，不可有 . 或 .. 或空白的路徑片段
+    fn check_path(template: &str, parts: &[Part]) -> Result<(), TemplateError> {
+        let shape: String = parts.iter().map(|part| match part {
+            Part::Literal(literal) => literal.as_str(),
+            Part::Field(_, _) => "0",
+        }).collect();
+
+        if shape.contains('\\') || shape.contains(':') {
+            return Err(TemplateError(format!("'\\' and ':' are not allowed in {}", template)));
+        }
+        if shape.split('/').any(|segment| segment.is_empty() || segment == "." || segment == "..") {
+            return Err(TemplateError(format!("absolute paths, empty, '.' and '..' segments are not allowed in {}", template)));
+        }
+
+        Ok(())
+    }
+
+    fn parse_field(field: &str) -> Result<Part, TemplateError> {
+        let (name, divisor) = match field.find('/') {
+            Some(i) => match field[i + 1..].trim().parse::<u32>() {
+                Ok(divisor) if divisor > 0 => (field[..i].trim(), Some(divisor)),
+                _ => return Err(TemplateError(format!("invalid divisor in {{{}}}", field))),
+            },
+            None => (field.trim(), None),
+        };
+
+        let field = match name {
+            "id" => Field::Id,
+            "map" => Field::Map,
+            "version" => Field::Version,
+            "width" => Field::Width,
+            "height" => Field::Height,
+            "palette" => Field::Palette,
+            _ => return Err(TemplateError(format!("unknown field {{{}}}", name))),
+        };
+        if field == Field::Palette && divisor.is_some() {
+            return Err(TemplateError("{palette} cannot be divided".to_string()));
+        }
+
+        Ok(Part::Field(field, divisor))
+    }
+
+    pub fn contains(&self, field: Field) -> bool {
+        self.0.iter().any(|part| match part {
+            Part::Field(f, _) => *f == field,
+            _ => false,
+        })
+    }
+
+    pub fn render(&self, fields: &NameFields) -> String {
+        let mut ret = String::new();
+
+        for part in &self.0 {
+            let value = match part {
+                Part::Literal(literal) => {
+                    ret.push_str(literal);
+                    continue;
+                },
+                Part::Field(Field::Palette, _) => {
+                    ret.push_str(&safe_segment(fields.palette));
+                    continue;
+                },
+                Part::Field(Field::Id, divisor) => (fields.id, divisor),
+                Part::Field(Field::Map, divisor) => (fields.map, divisor),
+                Part::Field(Field::Version, divisor) => (fields.version as u32, divisor),
+                Part::Field(Field::Width, divisor) => (fields.width, divisor),
+                Part::Field(Field::Height, divisor) => (fields.height, divisor),
+            };
+            match value {
+                (value, Some(divisor)) => ret.push_str(&(value / divisor).to_string()),
+                (value, None) => ret.push_str(&value.to_string()),
+            }
+        }
+
+        ret
+    }
+}
+
+// 調色盤名稱來自檔名，替換可能離開輸出目錄的字元
+fn safe_segment(value: &str) -> String {
+    let ret: String = value.chars().map(|c| if ['/', '\\', ':'].contains(&c) { '_' } else { c }).collect();
+    match ret.as_str() {
+        "" => "_".to_string(),
+        _ if ret.chars().all(|c| c == '.') => ret.replace('.', "_"),
+        _ => ret,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn fields() -> NameFields<'static> {
+        NameFields {id: 12345, map: 999, version: 1, width: 64, height: 47, palette: "palet_00"}
+    }
+
+    #[test]
+    fn render_template() {
+        let template = NameTemplate::parse("{map}/{id/1000}/{id}_{width}x{height}_{palette}_v{version}").unwrap();
+
+        assert_eq!("999/12/12345_64x47_palet_00_v1", template.render(&fields()));
+        assert!(template.contains(Field::Palette));
+    }
+
+    #[test]
+    fn parse_invalid_template() {
+        assert!(NameTemplate::parse("").is_err());
+        assert!(NameTemplate::parse("{id").is_err());
+        assert!(NameTemplate::parse("{name}").is_err());
+        assert!(NameTemplate::parse("{id/0}").is_err());
+        assert!(NameTemplate::parse("{palette/10}").is_err());
+    }
+
+    #[test]
+    fn reject_unsafe_template() {
+        assert!(NameTemplate::parse("{map}").is_err());
+        assert!(NameTemplate::parse("{id/1000}").is_err());
+        assert!(NameTemplate::parse("../{id}").is_err());
+        assert!(NameTemplate::parse("{map}/../{id}").is_err());
+        assert!(NameTemplate::parse("/tmp/{id}").is_err());
+        assert!(NameTemplate::parse("{map}//{id}").is_err());
+        assert!(NameTemplate::parse("{map}\\{id}").is_err());
+        assert!(NameTemplate::parse("C:{id}").is_err());
+        assert!(NameTemplate::parse("{map}/..{id}").is_ok());
+    }
+
+    #[test]
+    fn sanitize_palette() {
+        let template = NameTemplate::parse("{palette}/{id}").unwrap();
+        let render = |palette| template.render(&NameFields {palette, ..fields()});
+
+        assert_eq!("__/12345", render(".."));
+        assert_eq!(".._x/12345", render("../x"));
+        assert_eq!("_/12345", render(""));
+    }
+}
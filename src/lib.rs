@@ -1,9 +1,14 @@
+#[cfg(feature = "cli")]
 use fern::colors::{Color, ColoredLevelConfig};
 
 pub mod data_structure;
 pub mod resource;
+#[cfg(feature = "cli")]
 pub mod features;
 
+pub use resource::archive::Archive;
+
+#[cfg(feature = "cli")]
 pub fn logger_init(level: log::LevelFilter) -> Result<(), fern::InitError> {
     let color = ColoredLevelConfig::default().info(Color::Green);
 
@@ -1,18 +1,36 @@
+use std::env;
 use std::error::Error;
+use std::ffi::OsString;
+use std::path::Path;
 use clap::{App, load_yaml};
-use log::{error, info};
+use log::{debug, error, info};
 use xgate_tool::{
     logger_init,
     features::{
         info::show_info,
         dump::dump_graphics,
+        palette::convert_palette,
+        index::open_indexed,
+        config::{Config, list_sets},
     },
-    resource::graphic::{
-        GraphicInfoResource, GraphicResource, PaletteResource
-    }
+    resource::archive::Archive,
 };
+#[cfg(feature = "png")]
+use xgate_tool::resource::cache::GraphicCache;
+#[cfg(feature = "png")]
+use xgate_tool::features::atlas::pack_atlas;
+#[cfg(feature = "png")]
+use xgate_tool::features::gallery::build_gallery;
+#[cfg(feature = "png")]
+use xgate_tool::features::serve::serve_archive;
+#[cfg(feature = "png")]
+use xgate_tool::features::browse::browse_graphics;
+#[cfg(feature = "png")]
+use xgate_tool::features::montage::build_montage;
+#[cfg(feature = "png")]
+use xgate_tool::features::find::find_image;
 
-fn main() -> Result<(), Box<dyn Error>> {
+fn main() -> Result<(), Box<dyn Error + Send + Sync>> {
     let config = load_yaml!("../config/conf.yaml");
     let app = App::from_yaml(config).get_matches();
 
@@ -23,7 +41,23 @@ fn main() -> Result<(), Box<dyn Error>> {
         3|_ => logger_init(log::LevelFilter::Trace)?,
     }
 
-    match run(app) {
+    let settings = match Config::find(app.value_of("config")) {
+        Err(e) => {
+            error!("{:?}", e);
+            return Err(e);
+        },
+        Ok(settings) => settings,
+    };
+    // 設定檔中的預設輸出位置，附加 --output 後重新解析，與手動指定相同
+    let app = match settings.default_output(&app) {
+        Some(output) => {
+            let args = env::args_os().chain(vec![OsString::from("--output"), output.into_os_string()]);
+            App::from_yaml(config).get_matches_from(args)
+        },
+        None => app,
+    };
+
+    match run(app, &settings) {
         Err(e) => {
             error!("{:?}", e);
             return Err(e);
@@ -34,23 +68,76 @@ fn main() -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
-fn run(app: clap::ArgMatches) -> Result<(), Box<dyn Error>> {
+fn run(app: clap::ArgMatches, settings: &Config) -> Result<(), Box<dyn Error + Send + Sync>> {
+    // 調色盤的轉換不需要讀取 <GraphicInfo.bin> 及 <Graphic.bin>
+    if let ("palette", Some(sub_args)) = app.subcommand() {
+        info!("Converting the palette");
+        return convert_palette(sub_args, app.value_of("palette"));
+    }
+
+    let sets = settings.sets(app.value_of("game_dir"))?;
+    if let ("sets", Some(_)) = app.subcommand() {
+        info!("Listing archive sets");
+        return list_sets(&sets, app.value_of("set").or_else(|| settings.default_set()));
+    }
+
     info!("Start loading resources");
-    let mut resources = (
-        GraphicInfoResource::load(app.value_of("GraphicInfo").unwrap())?,
-        GraphicResource::load(app.value_of("Graphic").unwrap())?,
-        PaletteResource::load(app.value_of("palette"))?,
-    );
+    let source = settings.resolve(&app, &sets)?;
+    debug!("{:?}", source);
+    let archive = match &source.index_cache {
+        Some(path) => open_indexed(&source.graphic_info, &source.graphic, source.palette.as_deref(), Path::new(path))?,
+        None => Archive::open(&source.graphic_info, &source.graphic, source.palette.as_deref())?,
+    };
+    // 常駐的伺服器及瀏覽介面會重複存取相同的圖片，快取解碼及轉換後的結果
+    #[cfg(feature = "png")]
+    let archive = match app.subcommand() {
+        ("serve", Some(sub_args)) | ("browse", Some(sub_args)) => {
+            let budget = sub_args.value_of("cache_size").unwrap_or("64").parse::<usize>()?;
+            let budget = budget.checked_mul(1024 * 1024).ok_or("--cache-size is too large")?;
+            archive.with_cache(GraphicCache::new(budget, true))
+        },
+        _ => archive,
+    };
     info!("Resources loaded");
 
     match app.subcommand() {
         ("info", Some(sub_args)) => {
             info!("Parsing informations of <GraphicInfo.bin> and <Graphic.bin>");
-            show_info(sub_args, &mut resources)?;
+            show_info(sub_args, &archive)?;
         },
         ("dump", Some(sub_args)) => {
             info!("Dumping the graphic");
-            dump_graphics(sub_args, &mut resources)?;
+            dump_graphics(sub_args, &archive)?;
+        },
+        #[cfg(feature = "png")]
+        ("atlas", Some(sub_args)) => {
+            info!("Packing the graphics into atlas");
+            pack_atlas(sub_args, &archive)?;
+        },
+        #[cfg(feature = "png")]
+        ("montage", Some(sub_args)) => {
+            info!("Laying out the graphics into montage");
+            build_montage(sub_args, &archive)?;
+        },
+        #[cfg(feature = "png")]
+        ("find-image", Some(sub_args)) => {
+            info!("Finding the graphics by image");
+            find_image(sub_args, &archive)?;
+        },
+        #[cfg(feature = "png")]
+        ("gallery", Some(sub_args)) => {
+            info!("Generating the gallery");
+            build_gallery(sub_args, &archive)?;
+        },
+        #[cfg(feature = "png")]
+        ("serve", Some(sub_args)) => {
+            info!("Serving the graphics over HTTP");
+            serve_archive(sub_args, &archive)?;
+        },
+        #[cfg(feature = "png")]
+        ("browse", Some(sub_args)) => {
+            info!("Browsing the graphics");
+            browse_graphics(sub_args, &archive)?;
         },
         _ => {}
     }
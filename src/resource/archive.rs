@@ -0,0 +1,259 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::io;
+use std::sync::Arc;
+use log::{info, debug};
+use crate::data_structure::graphic::{GraphicInfo, GraphicHeader, Graphic, GraphicData, Palette};
+use crate::data_structure::image::{IndexedImage, RgbaImage};
+use crate::data_structure::scale::Scale;
+use crate::resource::cache::{GraphicCache, CacheStats};
+use crate::resource::graphic::{GraphicInfoResource, GraphicResource, PaletteResource};
+
+pub const DEFAULT_PALETTE_NAME: &str = "default";
+
+pub type Result<T> = std::result::Result<T, Box<dyn Error + Send + Sync>>;
+
+// 使用位置讀取（read_at）存取 <Graphic.bin>，不共用檔案游標，因此可跨執行緒共用同一個 Archive
+pub struct Archive {
+    infos: Vec<GraphicInfo>,
+    index: HashMap<u32, usize>,
+    graphic: GraphicResource,
+    palettes: Vec<(String, Palette)>,
+    default_palette: Palette,
+    cache: Option<GraphicCache>,
+    // 由索引快取載入，以位址對應標頭，以 id 對應內容雜湊
+    headers: HashMap<u32, GraphicHeader>,
+    payloads: HashMap<u32, String>,
+}
+
+impl Archive {
+    pub fn open(graphic_info: &str, graphic: &str, palette: Option<&str>) -> Result<Self> {
+        Self::new(
+            GraphicInfoResource::load(graphic_info)?,
+            GraphicResource::load(graphic)?,
+        )?.with_palettes(palette)
+    }
+
+    pub fn new(graphic_info: GraphicInfoResource, graphic: GraphicResource) -> Result<Self> {
+        info!("Collecting all of GraphicInfo");
+        let infos = graphic_info.read_all()?;
+        info!("Collected {} GraphicInfo", infos.len());
+
+        Ok(Self::from_infos(infos, graphic))
+    }
+
+    pub fn from_infos(infos: Vec<GraphicInfo>, graphic: GraphicResource) -> Self {
+        let mut index = HashMap::with_capacity(infos.len());
+        for (i, gi) in infos.iter().enumerate() {
+            // 與依序搜尋的行為一致，id 重複時以第一筆為準
+            index.entry(gi.id).or_insert(i);
+        }
+
+        Self {
+            infos,
+            index,
+            graphic,
+            palettes: vec![],
+            default_palette: Palette::default(),
+            cache: None,
+            headers: HashMap::new(),
+            payloads: HashMap::new(),
+        }
+    }
+
+    // 載入目錄或單一檔案中的所有調色盤
+    pub fn with_palettes(mut self, palette: Option<&str>) -> Result<Self> {
+        for (name, mut resource) in PaletteResource::load_all(palette)? {
+            self = self.with_palette(&name, resource.build()?);
+        }
+
+        Ok(self)
+    }
+
+    pub fn with_palette(mut self, name: &str, palette: Palette) -> Self {
+        self.palettes.push((name.to_string(), palette));
+        self
+    }
+
+    pub fn with_cache(mut self, cache: GraphicCache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    pub fn with_index(mut self, headers: Vec<(u32, GraphicHeader)>, payloads: Vec<(u32, String)>) -> Self {
+        self.headers = headers.into_iter().collect();
+        self.payloads = payloads.into_iter().collect();
+        self
+    }
+
+    // 未載入任何調色盤時，使用內建的預設調色盤
+    pub fn palette(&self) -> &Palette {
+        match self.palettes.first() {
+            Some((_, palette)) => palette,
+            None => &self.default_palette,
+        }
+    }
+
+    pub fn palette_name(&self) -> &str {
+        match self.palettes.first() {
+            Some((name, _)) => name,
+            None => DEFAULT_PALETTE_NAME,
+        }
+    }
+
+    pub fn palettes(&self) -> Vec<(&str, &Palette)> {
+        if self.palettes.is_empty() {
+            return vec![(DEFAULT_PALETTE_NAME, &self.default_palette)];
+        }
+
+        self.palettes.iter().map(|(name, palette)| (name.as_str(), palette)).collect()
+    }
+
+    pub fn find_palette(&self, name: &str) -> Option<&Palette> {
+        self.palettes()
+            .into_iter()
+            .find(|(n, _)| *n == name)
+            .map(|(_, palette)| palette)
+    }
+
+    pub fn cache_stats(&self) -> Option<CacheStats> {
+        self.cache.as_ref().map(|cache| cache.stats())
+    }
+
+    pub fn infos(&self) -> &[GraphicInfo] {
+        &self.infos
+    }
+
+    pub fn info(&self, id: u32) -> Option<&GraphicInfo> {
+        self.index.get(&id).map(|&i| &self.infos[i])
+    }
+
+    // <Graphic.bin> 的檔案資訊，用於判斷快取是否過期
+    pub fn graphic_metadata(&self) -> io::Result<std::fs::Metadata> {
+        self.graphic.metadata()
+    }
+
+    pub fn payload_hash(&self, id: u32) -> Option<&str> {
+        self.payloads.get(&id).map(|hash| hash.as_str())
+    }
+
+    pub fn header(&self, id: u32) -> Result<GraphicHeader> {
+        self.read_header(self.require(id)?)
+    }
+
+    pub fn raw(&self, id: u32) -> Result<Graphic> {
+        self.read_graphic(self.require(id)?)
+    }
+
+    pub fn decoded(&self, id: u32) -> Result<Arc<GraphicData>> {
+        self.decode(self.require(id)?)
+    }
+
+    pub fn indexed(&self, id: u32) -> Result<Option<IndexedImage>> {
+        let graphic_info = self.require(id)?;
+
+        Ok(IndexedImage::build(graphic_info, &*self.decode(graphic_info)?)?)
+    }
+
+    pub fn render(&self, id: u32, palette: &Palette) -> Result<Option<Arc<RgbaImage>>> {
+        self.build_image(self.require(id)?, palette)
+    }
+
+    // 放大後的圖片不會被快取
+    pub fn render_scaled(&self, id: u32, palette: &Palette, scale: Scale) -> Result<Option<RgbaImage>> {
+        Ok(self.render(id, palette)?.map(|image| image.scaled(scale)).transpose()?)
+    }
+
+    pub fn iter_headers(&self) -> impl Iterator<Item = (&GraphicInfo, Result<GraphicHeader>)> + '_ {
+        self.infos.iter().map(move |gi| (gi, self.read_header(gi)))
+    }
+
+    pub fn iter_raw(&self) -> impl Iterator<Item = (&GraphicInfo, Result<Graphic>)> + '_ {
+        self.infos.iter().map(move |gi| (gi, self.read_graphic(gi)))
+    }
+
+    pub fn iter_decoded(&self) -> impl Iterator<Item = (&GraphicInfo, Result<Arc<GraphicData>>)> + '_ {
+        self.infos.iter().map(move |gi| (gi, self.decode(gi)))
+    }
+
+    pub fn iter_render<'a>(&'a self, palette: &'a Palette) -> impl Iterator<Item = (&'a GraphicInfo, Result<Option<Arc<RgbaImage>>>)> + 'a {
+        self.infos.iter().map(move |gi| (gi, self.build_image(gi, palette)))
+    }
+
+    fn read_header(&self, info: &GraphicInfo) -> Result<GraphicHeader> {
+        if let Some(header) = self.headers.get(&info.address) {
+            return Ok(header.clone());
+        }
+
+        debug!("Finding graphic_header at {}", info.address);
+        let header = self.graphic.read_header_at(info.address as u64)?;
+        debug!("Found graphic_header = {:?}", header);
+
+        Ok(header)
+    }
+
+    fn read_graphic(&self, info: &GraphicInfo) -> Result<Graphic> {
+        match self.read_header(info)?.version {
+            0 | 1 => Ok(Graphic::new_v1(
+                self.graphic.read_at(info.address as u64, info.length as usize)?,
+            )?),
+            version => Err(format!("Unsupported version of graphic: {} (id: {})", version, info.id).into()),
+        }
+    }
+
+    fn decode(&self, info: &GraphicInfo) -> Result<Arc<GraphicData>> {
+        if let Some(data) = self.cache.as_ref().and_then(|cache| cache.decoded(info.id)) {
+            return Ok(data);
+        }
+
+        let mut graphic = self.read_graphic(info)?;
+        if graphic.header.version & 1 == 1 {
+            graphic.data = graphic.data.decode();
+        }
+        let data = Arc::new(graphic.data);
+        if let Some(cache) = &self.cache {
+            cache.insert_decoded(info.id, data.clone());
+        }
+
+        Ok(data)
+    }
+
+    fn build_image(&self, info: &GraphicInfo, palette: &Palette) -> Result<Option<Arc<RgbaImage>>> {
+        if let Some(image) = self.cache.as_ref().and_then(|cache| cache.rendered(info.id, palette)) {
+            return Ok(Some(image));
+        }
+
+        let image = match RgbaImage::build(info, &*self.decode(info)?, palette)? {
+            Some(image) => Arc::new(image),
+            None => return Ok(None),
+        };
+        if let Some(cache) = &self.cache {
+            cache.insert_rendered(info.id, palette, image.clone());
+        }
+
+        Ok(Some(image))
+    }
+
+    fn require(&self, id: u32) -> Result<&GraphicInfo> {
+        debug!("Finding graphic by id = {}", id);
+        match self.info(id) {
+            Some(graphic_info) => Ok(graphic_info),
+            None => Err(Box::new(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("Graphic not found (id: {})", id),
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn archive_is_send_and_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+
+        assert_send_sync::<Archive>();
+    }
+}
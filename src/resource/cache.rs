@@ -0,0 +1,204 @@
+use std::collections::{BTreeMap, HashMap};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::sync::atomic::{AtomicU64, Ordering};
+use log::trace;
+use crate::data_structure::graphic::{GraphicData, Palette};
+use crate::data_structure::image::RgbaImage;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum CacheKey {
+    Decoded(u32),
+    Rendered(u32, u64),
+}
+
+#[derive(Clone)]
+enum CacheValue {
+    Decoded(Arc<GraphicData>),
+    Rendered(Arc<RgbaImage>),
+}
+
+impl CacheValue {
+    fn size(&self) -> usize {
+        match self {
+            CacheValue::Decoded(data) => data.bytes().len(),
+            CacheValue::Rendered(image) => image.pixels.len(),
+        }
+    }
+}
+
+struct Entry {
+    value: CacheValue,
+    last_used: u64,
+}
+
+#[derive(Default)]
+struct Inner {
+    entries: HashMap<CacheKey, Entry>,
+    recency: BTreeMap<u64, CacheKey>,
+    tick: u64,
+    bytes: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub entries: usize,
+    pub bytes: usize,
+}
+
+// 以位元組數為上限的 LRU 快取，存放解碼後的色號資料，以及（選擇性）轉換後的 RGBA 圖片
+pub struct GraphicCache {
+    budget: usize,
+    rendered: bool,
+    inner: Mutex<Inner>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl GraphicCache {
+    pub fn new(budget: usize, rendered: bool) -> Self {
+        Self {
+            budget,
+            rendered,
+            inner: Mutex::new(Inner::default()),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        let inner = self.lock();
+
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            entries: inner.entries.len(),
+            bytes: inner.bytes,
+        }
+    }
+
+    pub fn decoded(&self, id: u32) -> Option<Arc<GraphicData>> {
+        match self.get(CacheKey::Decoded(id)) {
+            Some(CacheValue::Decoded(data)) => Some(data),
+            _ => None,
+        }
+    }
+
+    pub fn insert_decoded(&self, id: u32, data: Arc<GraphicData>) {
+        self.insert(CacheKey::Decoded(id), CacheValue::Decoded(data));
+    }
+
+    pub fn rendered(&self, id: u32, palette: &Palette) -> Option<Arc<RgbaImage>> {
+        if !self.rendered {
+            return None;
+        }
+
+        match self.get(CacheKey::Rendered(id, fingerprint(palette))) {
+            Some(CacheValue::Rendered(image)) => Some(image),
+            _ => None,
+        }
+    }
+
+    pub fn insert_rendered(&self, id: u32, palette: &Palette, image: Arc<RgbaImage>) {
+        if self.rendered {
+            self.insert(CacheKey::Rendered(id, fingerprint(palette)), CacheValue::Rendered(image));
+        }
+    }
+
+    fn get(&self, key: CacheKey) -> Option<CacheValue> {
+        let mut inner = self.lock();
+        inner.tick += 1;
+        let tick = inner.tick;
+
+        let (value, last_used) = match inner.entries.get_mut(&key) {
+            Some(entry) => {
+                let last_used = entry.last_used;
+                entry.last_used = tick;
+                (entry.value.clone(), last_used)
+            },
+            None => {
+                self.misses.fetch_add(1, Ordering::Relaxed);
+                return None;
+            },
+        };
+        inner.recency.remove(&last_used);
+        inner.recency.insert(tick, key);
+        self.hits.fetch_add(1, Ordering::Relaxed);
+
+        Some(value)
+    }
+
+    fn insert(&self, key: CacheKey, value: CacheValue) {
+        let size = value.size();
+        if size > self.budget {
+            trace!("Skip caching {:?}, {} bytes exceeds the budget", key, size);
+            return;
+        }
+
+        let mut inner = self.lock();
+        inner.tick += 1;
+        let tick = inner.tick;
+
+        if let Some(old) = inner.entries.insert(key, Entry {value, last_used: tick}) {
+            inner.recency.remove(&old.last_used);
+            inner.bytes -= old.value.size();
+        }
+        inner.recency.insert(tick, key);
+        inner.bytes += size;
+
+        while inner.bytes > self.budget {
+            let (&oldest, &evicted) = match inner.recency.iter().next() {
+                Some(first) => first,
+                None => break,
+            };
+            inner.recency.remove(&oldest);
+            if let Some(entry) = inner.entries.remove(&evicted) {
+                inner.bytes -= entry.value.size();
+            }
+            trace!("Evicted {:?} from graphic cache", evicted);
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Inner> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+fn fingerprint(palette: &Palette) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    palette.hash(&mut hasher);
+
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn evict_least_recently_used() {
+        let cache = GraphicCache::new(8, false);
+        cache.insert_decoded(1, Arc::new(GraphicData::from(vec![0; 4])));
+        cache.insert_decoded(2, Arc::new(GraphicData::from(vec![0; 4])));
+        assert!(cache.decoded(1).is_some());
+
+        cache.insert_decoded(3, Arc::new(GraphicData::from(vec![0; 4])));
+
+        assert!(cache.decoded(1).is_some());
+        assert!(cache.decoded(2).is_none());
+        assert!(cache.decoded(3).is_some());
+        assert_eq!(CacheStats {hits: 3, misses: 1, entries: 2, bytes: 8}, cache.stats());
+    }
+
+    #[test]
+    fn skip_entry_larger_than_budget() {
+        let cache = GraphicCache::new(8, false);
+        cache.insert_decoded(1, Arc::new(GraphicData::from(vec![0; 16])));
+
+        assert!(cache.decoded(1).is_none());
+        assert_eq!(0, cache.stats().bytes);
+    }
+}
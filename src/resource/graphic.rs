@@ -1,6 +1,7 @@
 use std::io;
 use std::io::{Read, Seek, SeekFrom};
-use std::fs::File;
+use std::fs::{self, File};
+use std::path::Path;
 use log::{info, warn};
 use crate::data_structure::graphic::{GraphicInfo, GraphicHeader, Palette};
 
@@ -13,6 +14,15 @@ impl GraphicInfoResource {
         info!("Loading <GraphicInfo.bin> from {}", path);
         Ok(GraphicInfoResource(File::open(path)?))
     }
+
+    pub fn read_all(&self) -> Result<Vec<GraphicInfo>, Box<bincode::ErrorKind>> {
+        let mut buf = vec![0; self.0.metadata()?.len() as usize];
+        read_exact_at(&self.0, &mut buf, 0)?;
+
+        buf.chunks_exact(40)
+            .map(bincode::deserialize::<GraphicInfo>)
+            .collect()
+    }
 }
 
 impl Iterator for GraphicInfoResource {
@@ -48,18 +58,77 @@ impl GraphicResource {
 
         Ok(data)
     }
+
+    pub fn metadata(&self) -> io::Result<fs::Metadata> {
+        self.0.metadata()
+    }
+
+    // 以下為不移動檔案游標的讀取方式，可於多個執行緒中共用同一個 GraphicResource
+    pub fn read_header_at(&self, address: u64) -> Result<GraphicHeader, Box<bincode::ErrorKind>> {
+        bincode::deserialize(&self.read_at(address, 16)?)
+    }
+
+    pub fn read_at(&self, address: u64, size: usize) -> Result<Vec<u8>, io::Error> {
+        let mut data = vec![0; size];
+        read_exact_at(&self.0, &mut data, address)?;
+
+        Ok(data)
+    }
 }
 
 impl PaletteResource {
     pub fn load(path: Option<&str>) -> Result<Option<Self>, io::Error> {
-        info!("Loading <Palet.cgp>");
         match path {
-            Some(path) => return Ok(Some(PaletteResource(File::open(path)?))),
+            Some(path) => {
+                info!("Loading <Palet.cgp> from {}", path);
+                Ok(Some(PaletteResource(File::open(path)?)))
+            },
+            None => {
+                warn!("Empty path of <Palet.cgp>, using the built-in default palette");
+                Ok(None)
+            },
+        }
+    }
+
+    // path 可為單一 <Palet.cgp> 或存放多個 *.cgp 的目錄，以檔名（不含副檔名）作為調色盤名稱
+    pub fn load_all(path: Option<&str>) -> Result<Vec<(String, Self)>, io::Error> {
+        let path = match path {
+            Some(path) => Path::new(path),
             None => {
-                warn!("Empty path of <Palet.cgp>");
-                return Ok(None)
+                warn!("Empty path of <Palet.cgp>, using the built-in default palette");
+                return Ok(vec![]);
             },
+        };
+
+        let mut paths = if path.is_dir() {
+            info!("Loading *.cgp from {}", path.display());
+            let mut paths = vec![];
+            for entry in fs::read_dir(path)? {
+                let entry = entry?.path();
+                let is_cgp = entry.extension()
+                    .map(|ext| ext.eq_ignore_ascii_case("cgp"))
+                    .unwrap_or(false);
+                if entry.is_file() && is_cgp {
+                    paths.push(entry);
+                }
+            }
+            if paths.is_empty() {
+                return Err(io::Error::new(io::ErrorKind::NotFound, format!("No *.cgp found in {}", path.display())));
+            }
+            paths
+        } else {
+            vec![path.to_path_buf()]
+        };
+        paths.sort();
+
+        let mut ret = vec![];
+        for path in paths {
+            info!("Loading <Palet.cgp> from {}", path.display());
+            let name = path.file_stem().unwrap_or_default().to_string_lossy().into_owned();
+            ret.push((name, PaletteResource(File::open(&path)?)));
         }
+
+        Ok(ret)
     }
 
     pub fn build(&mut self) -> Result<Palette, io::Error> {
@@ -68,4 +137,30 @@ impl PaletteResource {
 
         Ok(Palette::new(&buf))
     }
-}
\ No newline at end of file
+}
+
+#[cfg(unix)]
+fn read_exact_at(file: &File, buf: &mut [u8], offset: u64) -> io::Result<()> {
+    use std::os::unix::fs::FileExt;
+
+    file.read_exact_at(buf, offset)
+}
+
+#[cfg(windows)]
+fn read_exact_at(file: &File, mut buf: &mut [u8], mut offset: u64) -> io::Result<()> {
+    use std::os::windows::fs::FileExt;
+
+    while !buf.is_empty() {
+        match file.seek_read(buf, offset) {
+            Ok(0) => return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "failed to fill whole buffer")),
+            Ok(n) => {
+                buf = &mut buf[n..];
+                offset += n as u64;
+            },
+            Err(ref e) if e.kind() == io::ErrorKind::Interrupted => {},
+            Err(e) => return Err(e),
+        }
+    }
+
+    Ok(())
+}
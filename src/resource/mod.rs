@@ -1 +1,3 @@
-pub mod graphic;
\ No newline at end of file
+pub mod graphic;
+pub mod archive;
+pub mod cache;
\ No newline at end of file